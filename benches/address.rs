@@ -0,0 +1,20 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use kromer_api::model::{Address, PrivateKey};
+use std::hint::black_box;
+
+fn derive_addresses(c: &mut Criterion) {
+    let keys: Vec<_> = (0..10_000)
+        .map(|i| PrivateKey::new(&format!("bench-key-{i}")))
+        .collect();
+
+    c.bench_function("derive 10k addresses", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(Address::from(black_box(key)));
+            }
+        });
+    });
+}
+
+criterion_group!(benches, derive_addresses);
+criterion_main!(benches);
@@ -16,7 +16,7 @@ async fn main() -> Result<(), Error> {
     let pg = Paginator::new(0, 1);
 
     let wallet = client
-        .recent_wallet_transactions(&res[0].address, false, Some(&pg))
+        .recent_wallet_transactions(&res[0].addr(), false, Some(&pg))
         .await?;
 
     println!("{wallet:#?}");
@@ -0,0 +1,26 @@
+use kromer_api::{
+    Error,
+    http::{Paginator, blocking::Client},
+};
+
+fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt::init();
+
+    let client = Client::new("https://kromer.reconnected.cc")?;
+
+    let motd = client.get_motd()?;
+
+    println!("{}", motd.msg);
+
+    let res = client.get_wallet_name("laincy")?;
+
+    println!("{res:#?}");
+
+    let pg = Paginator::new(0, 1);
+
+    let transactions = client.recent_wallet_transactions(&res[0].addr(), false, Some(&pg))?;
+
+    println!("{transactions:#?}");
+
+    Ok(())
+}
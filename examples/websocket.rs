@@ -54,7 +54,7 @@ async fn event_loop(
             WebSocketEvent::Transaction { transaction } => {
                 let wallet: Wallet;
                 if transaction.to == addr {
-                    wallet = client.get_wallet(&transaction.to).await?;
+                    wallet = client.get_wallet(&addr).await?;
                 } else if let Some(from) = transaction.from
                     && from == addr
                 {
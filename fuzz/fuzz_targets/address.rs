@@ -0,0 +1,15 @@
+#![no_main]
+
+use kromer_api::model::Address;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(addr) = Address::parse(data) {
+        // Display relies on the parsed bytes being valid ASCII, so a parsed
+        // address must always print back to its input
+        let s = addr.to_string();
+
+        assert_eq!(s.as_bytes(), data);
+        assert_eq!(Address::parse(s.as_bytes()).ok(), Some(addr));
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use kromer_api::model::krist::Name;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(name) = Name::parse(data) {
+        let s = name.to_string();
+
+        assert!(s.is_ascii());
+        assert_eq!(Name::parse(&s).ok(), Some(name));
+    }
+});
@@ -0,0 +1,185 @@
+//! Time sources used by the time-dependent helpers in this crate
+//!
+//! Anything that compares against the current time, such as filtering by a time
+//! window or waiting for a deadline, reads it from the [`Clock`] configured on
+//! the [`Client`](crate::http::Client) rather than calling [`Utc::now`]
+//! directly. Delays and timeouts, such as between retries or polls, wait on
+//! `Clock::sleep` rather than a timer of their own. This allows tests to
+//! control time with a [`MockClock`].
+//!
+//! Waiting needs a timer, so `Clock::sleep` and everything that waits on it is
+//! only available with the `tokio` feature.
+
+use chrono::{DateTime, Utc};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+    task::Waker,
+};
+
+/// The future returned by [`Clock::sleep`]
+#[cfg(feature = "tokio")]
+pub type Sleep = std::pin::Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of the current time
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Waits until `duration` has passed. Defaults to a Tokio timer
+    #[cfg(feature = "tokio")]
+    fn sleep(&self, duration: std::time::Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Runs `fut` until it completes or `duration` has passed on `clock`,
+/// returning `None` if it ran out of time
+#[cfg(feature = "websocket")]
+pub(crate) async fn timeout<F: Future>(
+    clock: &dyn Clock,
+    duration: std::time::Duration,
+    fut: F,
+) -> Option<F::Output> {
+    use futures_util::future::{Either, select};
+
+    match select(std::pin::pin!(fut), clock.sleep(duration)).await {
+        Either::Left((res, _)) => Some(res),
+        Either::Right(_) => None,
+    }
+}
+
+/// A [`Clock`] that reads the system time. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A [`Clock`] that only moves when told to. Clones share the same time, so a
+/// handle can be kept to advance a clock given to a client.
+///
+/// With the `tokio` feature, `Clock::sleep` waits until the clock has been
+/// moved past the end of the sleep, so a test decides exactly when delays and
+/// timeouts run out.
+///
+/// ```rust
+/// # use kromer_api::clock::{Clock, MockClock};
+/// # use chrono::{DateTime, TimeDelta};
+/// let clock = MockClock::new(DateTime::UNIX_EPOCH);
+/// clock.advance(TimeDelta::seconds(5));
+///
+/// assert_eq!(clock.now(), DateTime::UNIX_EPOCH + TimeDelta::seconds(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock(Arc<Mutex<MockState>>);
+
+#[derive(Debug)]
+struct MockState {
+    time: DateTime<Utc>,
+    /// Sleeps waiting for the time to move
+    sleepers: Vec<Waker>,
+}
+
+impl MockClock {
+    /// Creates a new [`Self`] starting at `time`
+    #[must_use]
+    pub fn new(time: DateTime<Utc>) -> Self {
+        Self(Arc::new(Mutex::new(MockState {
+            time,
+            sleepers: Vec::new(),
+        })))
+    }
+
+    /// Sets the current time to `time`
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.update(|now| *now = time);
+    }
+
+    /// Moves the current time forward by `delta`
+    pub fn advance(&self, delta: chrono::TimeDelta) {
+        self.update(|now| *now += delta);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut DateTime<Utc>)) {
+        let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut state.time);
+
+        for waker in state.sleepers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).time
+    }
+
+    #[cfg(feature = "tokio")]
+    fn sleep(&self, duration: std::time::Duration) -> Sleep {
+        use chrono::TimeDelta;
+        use futures_util::future;
+        use std::task::Poll;
+
+        let deadline = TimeDelta::from_std(duration)
+            .ok()
+            .and_then(|delta| self.now().checked_add_signed(delta))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let state = Arc::clone(&self.0);
+
+        Box::pin(future::poll_fn(move |cx| {
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+
+            if state.time >= deadline {
+                Poll::Ready(())
+            } else {
+                state.sleepers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }))
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::{Clock, MockClock};
+    use chrono::{DateTime, TimeDelta};
+    use futures_util::FutureExt;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn mock_sleep_waits_for_advance() {
+        let clock = MockClock::new(DateTime::UNIX_EPOCH);
+        let mut sleep = clock.sleep(Duration::from_secs(10));
+
+        assert!((&mut sleep).now_or_never().is_none());
+
+        clock.advance(TimeDelta::seconds(9));
+        assert!((&mut sleep).now_or_never().is_none());
+
+        clock.advance(TimeDelta::seconds(1));
+        assert!(sleep.now_or_never().is_some());
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn mock_timeout() {
+        use super::timeout;
+
+        let clock = MockClock::new(DateTime::UNIX_EPOCH);
+
+        let ready = timeout(&clock, Duration::from_secs(1), async { 1 }).await;
+        assert_eq!(ready, Some(1));
+
+        let pending = timeout(&clock, Duration::from_secs(1), std::future::pending::<()>());
+        let advance = async { clock.advance(TimeDelta::seconds(1)) };
+
+        let (res, ()) = futures_util::join!(pending, advance);
+        assert_eq!(res, None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Helpers for writing collections of models to files
+//!
+//! These consume the streams returned by methods such as
+//! [`Client::all_wallet_transactions`](crate::http::Client::all_wallet_transactions)
+//! and write each item as it arrives, so exports of any size use a constant
+//! amount of memory. Columns in CSV output, and keys in JSON output, follow the
+//! declaration order of the model's fields.
+//!
+//! ```rust
+//! # use kromer_api::{Error, http::Client, model::Address};
+//! # async fn run(client: Client<kromer_api::http::Basic>, addr: Address) -> Result<(), Error> {
+//! let file = std::fs::File::create("transactions.csv").unwrap();
+//! let stream = client.all_wallet_transactions(&addr, false);
+//!
+//! kromer_api::export::write_csv(stream, file).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Error;
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::{io::Write, pin::pin};
+
+/// Errors emitted while writing an export
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+#[allow(missing_docs)]
+pub enum ExportError {
+    /// Couldn't write to the output
+    #[snafu(display("Failed to write export"))]
+    Io { source: std::io::Error },
+    /// Couldn't serialize an item as CSV
+    #[snafu(display("Failed to serialize item as CSV"))]
+    Csv { source: csv::Error },
+    /// Couldn't serialize an item as JSON
+    #[snafu(display("Failed to serialize item as JSON"))]
+    Json { source: serde_json::Error },
+}
+
+/// Writes every item in `stream` to `writer` as newline delimited JSON,
+/// returning the number of items written
+///
+/// # Errors
+/// Errors if the stream yields an error, or writing to `writer` fails. Items
+/// received before the error will already have been written.
+///
+/// See [`Error`] for more info
+pub async fn write_jsonl<T, S, W>(stream: S, mut writer: W) -> Result<usize, Error>
+where
+    T: Serialize,
+    S: Stream<Item = Result<T, Error>>,
+    W: Write,
+{
+    let mut stream = pin!(stream);
+    let mut n = 0;
+
+    while let Some(item) = stream.next().await {
+        serde_json::to_writer(&mut writer, &item?).context(JsonSnafu)?;
+        writer.write_all(b"\n").context(IoSnafu)?;
+        n += 1;
+    }
+
+    writer.flush().context(IoSnafu)?;
+
+    Ok(n)
+}
+
+/// Writes every item in `stream` to `writer` as CSV with a header row,
+/// returning the number of items written
+///
+/// # Errors
+/// Errors if the stream yields an error, or writing to `writer` fails. Items
+/// received before the error will already have been written.
+///
+/// See [`Error`] for more info
+pub async fn write_csv<T, S, W>(stream: S, writer: W) -> Result<usize, Error>
+where
+    T: Serialize,
+    S: Stream<Item = Result<T, Error>>,
+    W: Write,
+{
+    let mut stream = pin!(stream);
+    let mut writer = csv::Writer::from_writer(writer);
+    let mut n = 0;
+
+    while let Some(item) = stream.next().await {
+        writer.serialize(item?).context(CsvSnafu)?;
+        n += 1;
+    }
+
+    writer.flush().context(IoSnafu)?;
+
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_csv, write_jsonl};
+    use crate::model::{Address, Wallet};
+    use futures_util::stream;
+    use rust_decimal::Decimal;
+
+    fn wallets() -> Vec<Result<Wallet, crate::Error>> {
+        [Address::Serverwelf, Address::parse(b"kdk1ku9oeq").unwrap()]
+            .into_iter()
+            .map(|address| Ok(Wallet::test(address, Decimal::new(1050, 2))))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn export_wallets() {
+        let mut out = Vec::new();
+        let n = write_csv(stream::iter(wallets()), &mut out).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(n, 2);
+        let header = if cfg!(feature = "camel-case") {
+            "address,balance,createdAt,locked,totalIn,totalOut"
+        } else {
+            "address,balance,created_at,locked,total_in,total_out"
+        };
+        assert_eq!(out.lines().next(), Some(header));
+        assert!(out.lines().nth(2).unwrap().starts_with("kdk1ku9oeq,10.50,"));
+
+        let mut out = Vec::new();
+        let n = write_jsonl(stream::iter(wallets()), &mut out)
+            .await
+            .unwrap();
+
+        assert_eq!(n, 2);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+    }
+}
@@ -1,5 +1,11 @@
 //! Types for interacting with Kromer2's HTTP API
 
+pub use builder::*;
+pub use capabilities::Capabilities;
+pub use lookup::DEFAULT_LOOKUP_CHUNK_SIZE;
+pub use rate_limit::RateLimitInfo;
+#[cfg(feature = "tokio")]
+pub use retry::RetryPolicy;
 use rust_decimal::Decimal;
 pub use util::*;
 
@@ -8,21 +14,28 @@ const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 const PKG_REPO: &str = env!("CARGO_PKG_REPOSITORY");
 
 use crate::{
-    BadRequestSnafu, BadUrlSnafu, Error, MalformedResponseSnafu, RequestFailedSnafu,
+    BadPreparedTransactionSnafu, BadRequestSnafu, CircuitOpenSnafu, Error, RequestFailedSnafu,
+    ResponseTooLargeSnafu,
+    clock::Clock,
+    metrics::Metrics,
     model::{
-        Address, PrivateKey, Wallet,
+        Address, Identifier, KromerError, ParseError, PrivateKey, Wallet, decimal, format_kromer,
         krist::{
-            KristError, Motd, Name, NameInfo, NamePage, SameWalletTransferSnafu, Transaction,
-            TransactionPage, WalletPage,
+            CounterpartySummary, KristError, Motd, Name, NameInfo, NamePage, Page,
+            PreparedTransaction, RegisterOutcome, Transaction, TransactionCost, TransactionPage,
+            TransactionType, WalletPage,
         },
     },
 };
-use reqwest::{Request, Response, header};
+use arc_swap::ArcSwapOption;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::{Stream, StreamExt, future, stream};
+use paginate::paginate;
+use reqwest::{Request, Response};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, ensure};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, pin::pin, sync::Arc, time::Instant};
 use tracing::{trace, warn};
-use url::Url;
 use uuid::Uuid;
 
 #[cfg(feature = "websocket")]
@@ -33,75 +46,255 @@ pub use internal::*;
 #[cfg(feature = "internal")]
 mod internal;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+mod builder;
+mod capabilities;
+mod circuit;
+mod endpoint;
 mod krist;
 mod kromer;
+mod lookup;
+mod paginate;
+mod rate_limit;
+#[cfg(feature = "tokio")]
+mod retry;
 mod util;
 
 pub(crate) use krist::RawKristError;
 
-use krist::{
-    AuthRequest, AuthRes, AvailRes, CostRes, ListTransactionsQuery, MakeTransactionBody, NameRes,
-    RegisterBody, SupplyRes, TransactionRes, TransferBody, UpdateBody,
-};
-use kromer::KromerResponse;
+use circuit::CircuitBreaker;
+use endpoint::{Api, Endpoint};
+use krist::ListTransactionsQuery;
 
 /// An HTTP client for calling the Kromer2 API. Reuses connections and parses
 /// responses into idiomatic rust types.
+///
+/// Every `Client` is `Send + Sync + 'static`, so a single one can be shared
+/// between tasks behind an [`Arc`].
 pub struct Client<M: ClientMarker> {
     url: url::Url,
     http: reqwest::Client,
+    lookup_chunk_size: usize,
+    max_response_bytes: usize,
+    tag_useragent: bool,
+    circuit: Option<CircuitBreaker>,
+    #[cfg(feature = "tokio")]
+    retry: Option<RetryPolicy>,
+    decimal_places: Option<u32>,
+    clock: Arc<dyn Clock>,
+    metrics: Option<Arc<dyn Metrics>>,
+    rate_limit: ArcSwapOption<RateLimitInfo>,
+    #[cfg(feature = "websocket")]
+    ws_tls: Arc<rustls::ClientConfig>,
     _marker: PhantomData<M>,
 }
 
+/// Shows the server and marker, e.g. `Client<Basic> { url: .. }`, leaving out
+/// the connection pool and other internals
+impl<M: ClientMarker> std::fmt::Debug for Client<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("Client<{}>", marker_name::<M>()))
+            .field("url", &self.url.as_str())
+            .field("decimal_places", &self.decimal_places)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The unqualified name of a marker type, e.g. `Basic`
+pub(crate) fn marker_name<M>() -> &'static str {
+    let name = std::any::type_name::<M>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Reports a request to [`Metrics`] as finished when dropped, so that requests
+/// cancelled by dropping their future are reported too
+struct InFlight<'a> {
+    metrics: &'a dyn Metrics,
+    path: String,
+    start: Instant,
+    status: Option<u16>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.metrics
+            .request_finished(&self.path, self.status, self.start.elapsed());
+    }
+}
+
 impl Client<Basic> {
     /// Create a new client for the Kromer2 API. This will reuse connections.
+    /// Use a [`ClientBuilder`] if you need to change any of its settings.
     ///
     /// # Errors
-    /// Errors if `url` is not a valid [`Url`]
+    /// Errors if `url` is not a valid [`Url`](url::Url)
     ///
     /// See [`Error`] for more info
     ///
     /// # Panics
     /// Panics if we cannot construct the client for an unknown reason. Chances
     /// are, if this occurs it is irrecoverable and an issue at the crate level
-    pub fn new(url: &str) -> Result<Self, Error> {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
-        );
-
-        let user_agent = format!("{PKG_NAME}/{PKG_VERSION} ({PKG_REPO})");
-
-        let client = Self {
-            url: Url::parse(url).context(BadUrlSnafu)?,
+    pub fn new(url: impl IntoUrl) -> Result<Self, Error> {
+        ClientBuilder::new().build(url)
+    }
 
-            // Safety:
-            // We can expect here because this should *never* fail unless something is fucked
-            #[allow(clippy::expect_used)]
-            http: reqwest::ClientBuilder::new()
-                .user_agent(user_agent)
-                .default_headers(headers)
-                .build()
-                .expect("HTTP is fucked, stop trying"),
+    /// Creates a new client for an already parsed `url`. Like [`Self::new`],
+    /// but can't fail.
+    ///
+    /// # Panics
+    /// Panics if we cannot construct the client for an unknown reason. Chances
+    /// are, if this occurs it is irrecoverable and an issue at the crate level
+    #[must_use]
+    pub fn from_url(url: url::Url) -> Self {
+        // Safety:
+        // The default builder always uses a valid user agent, which is all that can fail
+        #[allow(clippy::expect_used)]
+        ClientBuilder::new()
+            .build_with_headers(url, reqwest::header::HeaderMap::new())
+            .expect("default user agent is invalid")
+    }
 
-            _marker: PhantomData,
-        };
+    /// Creates a client that sends its requests through an existing
+    /// [`reqwest::Client`], such as one shared across an app with a proxy or
+    /// tracing configured. Other settings are the same as [`Self::new`].
+    ///
+    /// `http` is used as is, so setting a `User-Agent` is up to you. Request
+    /// bodies are always sent with a `Content-Type` of `application/json`.
+    ///
+    /// # Errors
+    /// Errors if `url` is not a valid [`Url`](url::Url)
+    ///
+    /// See [`Error`] for more info
+    pub fn from_parts(url: impl IntoUrl, http: reqwest::Client) -> Result<Self, Error> {
+        let url = url.into_url()?;
 
-        trace!("Initialized client for {}", client.url);
+        Ok(ClientBuilder::new().with_http(url, http))
+    }
 
-        Ok(client)
+    /// Creates a [`ClientBuilder`] for configuring a client
+    #[must_use]
+    pub const fn builder() -> ClientBuilder {
+        ClientBuilder::new()
     }
 }
 
 impl<M: ClientMarker> Client<M> {
+    /// The URL of the server this client sends its requests to
+    ///
+    /// ```rust
+    /// # use kromer_api::{Error, http::Client};
+    /// # fn run() -> Result<(), Error> {
+    /// let client = Client::new("https://kromer.reconnected.cc/")?;
+    ///
+    /// assert_eq!(client.base_url().as_str(), "https://kromer.reconnected.cc/");
+    /// # Ok(())
+    /// # }
+    /// # run().unwrap();
+    /// ```
+    #[must_use]
+    pub const fn base_url(&self) -> &url::Url {
+        &self.url
+    }
+
+    /// The [`Clock`] this client reads the current time from and waits on
+    #[must_use]
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// The rate limit information reported by the most recent response that
+    /// carried any, or `None` if the server has never sent it
+    #[must_use]
+    pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.rate_limit.load().as_deref().copied()
+    }
+
     /// General query behavior
     async fn query(&self, req: Request) -> Result<Response, Error> {
         trace!("sending a {} request to {}", req.method(), req.url());
-        let response = self.http.execute(req).await.context(RequestFailedSnafu)?;
 
+        if let Some(circuit) = &self.circuit {
+            ensure!(circuit.allow(self.clock.now()), CircuitOpenSnafu);
+        }
+
+        let Some(metrics) = &self.metrics else {
+            return self.send(req).await;
+        };
+
+        let mut in_flight = InFlight {
+            metrics: metrics.as_ref(),
+            path: req.url().path().to_string(),
+            start: Instant::now(),
+            status: None,
+        };
+        metrics.request_started(&in_flight.path);
+
+        let res = self.send(req).await;
+
+        in_flight.status = res.as_ref().ok().map(|r| r.status().as_u16());
+
+        res
+    }
+
+    /// Sends `req`, retrying it according to the client's [`RetryPolicy`] if
+    /// it is idempotent and fails in a way that might go away on its own
+    #[cfg(feature = "tokio")]
+    async fn send(&self, mut req: Request) -> Result<Response, Error> {
+        let Some(policy) = self.retry.filter(|_| retry::is_idempotent(req.method())) else {
+            return self.execute(req).await;
+        };
+
+        let mut attempt = 1;
+
+        loop {
+            let Some(next) = req.try_clone() else {
+                return self.execute(req).await;
+            };
+
+            let res = self.execute(req).await;
+
+            if !policy.allows_retry(attempt) || !retry::is_transient(&res) {
+                return res;
+            }
+
+            let delay = policy.delay(attempt);
+            warn!("retrying {} in {delay:?}", next.url());
+            self.clock.sleep(delay).await;
+
+            if let Some(circuit) = &self.circuit {
+                ensure!(circuit.allow(self.clock.now()), CircuitOpenSnafu);
+            }
+
+            req = next;
+            attempt += 1;
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn send(&self, req: Request) -> Result<Response, Error> {
+        self.execute(req).await
+    }
+
+    async fn execute(&self, req: Request) -> Result<Response, Error> {
+        let response = self.http.execute(req).await.context(RequestFailedSnafu);
+
+        if let Some(circuit) = &self.circuit {
+            let failed = response
+                .as_ref()
+                .map_or(true, |r| r.status().is_server_error());
+
+            circuit.record(!failed, self.clock.now());
+        }
+
+        let response = response?;
         let status = response.status();
 
+        if let Some(info) = RateLimitInfo::from_headers(response.headers(), self.clock.now()) {
+            self.rate_limit.store(Some(Arc::new(info)));
+        }
+
         if !status.is_success() {
             warn!("got HTTP code {} from {}", status, response.url());
         }
@@ -109,88 +302,111 @@ impl<M: ClientMarker> Client<M> {
         Ok(response)
     }
 
-    /// Get requests against the Kromer2 API
-    async fn get<T>(&self, endpoint: &str) -> Result<T, Error>
+    /// Reports the error in `res` to the installed [`Metrics`], if there is one
+    fn observe<T>(&self, res: Result<T, Error>) -> Result<T, Error> {
+        if let (Some(metrics), Err(e)) = (&self.metrics, &res) {
+            metrics.error(e);
+        }
+
+        res
+    }
+
+    /// Reads the body of `response` as `JSON`, giving up once it grows past
+    /// the client's [`max_response_bytes`](ClientBuilder::max_response_bytes)
+    async fn read_json<T>(&self, mut response: Response) -> Result<T, Error>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let url = self.url.join(endpoint).context(BadUrlSnafu)?;
+        let limit = self.max_response_bytes;
+        endpoint::check_declared_len(response.content_length(), limit)?;
 
-        let req = self.http.get(url).build().context(BadRequestSnafu)?;
+        let mut body = Vec::new();
 
-        Ok(self
-            .query(req)
-            .await?
-            .json::<KromerResponse<T>>()
-            .await
-            .context(MalformedResponseSnafu)?
-            .extract()?)
+        while let Some(chunk) = response.chunk().await.context(RequestFailedSnafu)? {
+            ensure!(
+                body.len() + chunk.len() <= limit,
+                ResponseTooLargeSnafu { limit }
+            );
+            body.extend_from_slice(&chunk);
+        }
+
+        endpoint::parse_json(&body, self.decimal_places)
     }
 
-    async fn krist_get<T>(
-        &self,
-        endpoint: &str,
-        query: Option<(impl Serialize + Send + Sync + Sized)>,
-    ) -> Result<T, Error>
+    /// Sends the request described by `endpoint`
+    async fn call<R, T, Q, B>(&self, endpoint: Endpoint<R, T, Q, B>) -> Result<T, Error>
     where
-        T: for<'de> Deserialize<'de>,
+        R: for<'de> Deserialize<'de>,
+        Q: Serialize + Send + Sync,
+        B: Serialize + Send + Sync,
     {
-        let url = self.url.join(endpoint).context(BadUrlSnafu)?;
+        let url = endpoint.url(&self.url);
+        let Endpoint {
+            api,
+            method,
+            query,
+            body,
+            finish,
+            ..
+        } = endpoint;
+
+        let req = url.and_then(|url| {
+            let mut req = self.http.request(method, url);
+
+            if let Some(query) = &query {
+                req = req.query(query);
+            }
+
+            if let Some(body) = &body {
+                req = req.json(body);
+            }
+
+            req.build().context(BadRequestSnafu)
+        });
 
-        let req = self
-            .http
-            .get(url)
-            .query(&query)
-            .build()
-            .context(BadRequestSnafu)?;
+        let res = async {
+            let response = self.query(req?).await?;
 
-        let response = self.query(req).await?;
+            let status = response.status();
 
-        if !response.status().is_success() {
-            response
-                .json::<RawKristError>()
-                .await
-                .context(MalformedResponseSnafu)?
-                .parse()?;
+            if api == Api::Krist && !status.is_success() {
+                let raw = self.read_json::<RawKristError>(response).await;
 
-            // Above will always return an Err
-            unreachable!()
+                return Err(krist::response_error(raw, status));
+            }
+
+            self.read_json::<R>(response).await
         }
+        .await;
 
-        response.json::<T>().await.context(MalformedResponseSnafu)
+        finish(self.observe(res))
     }
 
-    async fn krist_post<T>(
+    async fn krist_get<T>(
         &self,
         endpoint: &str,
-        body: impl Serialize + Send + Sync + Sized,
+        query: Option<impl Serialize + Send + Sync + Sized>,
     ) -> Result<T, Error>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let url = self.url.join(endpoint).context(BadUrlSnafu)?;
-
-        let req = self
-            .http
-            .post(url)
-            .json(&body)
-            .build()
-            .context(BadRequestSnafu)?;
-
-        let response = self.query(req).await?;
-
-        if !response.status().is_success() {
-            response
-                .json::<RawKristError>()
-                .await
-                .context(MalformedResponseSnafu)?
-                .parse()?;
+        self.call(Endpoint::get(endpoint).query(query)).await
+    }
 
-            // Above will always return an Err
-            unreachable!()
+    /// Fetches every [`Wallet`] `id` refers to, using the endpoint that
+    /// matches its kind. An [`Address`] resolves to a single wallet.
+    ///
+    /// # Errors
+    /// Errors if nothing matching `id` could be found, or there is a network
+    /// issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn wallets_for(&self, id: Identifier) -> Result<Vec<Wallet>, Error> {
+        match id {
+            Identifier::Address(addr) => Ok(vec![self.get_wallet_addr(&addr).await?]),
+            Identifier::Uuid(id) => self.get_wallet_uuid(&id).await,
+            Identifier::Username(name) => self.get_wallet_name(&name).await,
         }
-
-        response.json::<T>().await.context(MalformedResponseSnafu)
     }
 
     /// Fetches all [`Wallets`](Wallet) attached to a `Minecraft` `UUID`
@@ -200,8 +416,36 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn get_wallet_uuid(&self, id: &Uuid) -> Result<Vec<Wallet>, Error> {
-        let url = format!("/api/v1/wallet/by-uuid/{id}");
-        self.get(&url).await
+        self.call(endpoint::wallets_by_uuid(id)).await
+    }
+
+    /// Fetches the balance of each player's primary wallet, with at most
+    /// `concurrency` requests in flight at a time. A player's primary wallet is
+    /// their oldest. A failure for one player doesn't stop the others.
+    ///
+    /// Results are returned in the same order as `ids`, paired with the `UUID`
+    /// they belong to.
+    pub async fn balances_for_uuids(
+        &self,
+        ids: &[Uuid],
+        concurrency: usize,
+    ) -> Vec<(Uuid, Result<Decimal, Error>)> {
+        stream::iter(ids)
+            .map(|id| async move {
+                let res = self.get_wallet_uuid(id).await.and_then(|wallets| {
+                    wallets
+                        .into_iter()
+                        // Wallets without a creation time sort last
+                        .min_by_key(|w| (w.created_at.is_none(), w.created_at))
+                        .map(|w| w.balance)
+                        .ok_or_else(|| KromerError::ResourceNotFoundError.into())
+                });
+
+                (*id, res)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
     }
 
     /// Fetches all [`Wallets`](Wallet) attached to a `Minecraft` `username`
@@ -211,8 +455,7 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn get_wallet_name(&self, name: &str) -> Result<Vec<Wallet>, Error> {
-        let url = format!("/api/v1/wallet/by-name/{name}");
-        self.get(&url).await
+        self.call(endpoint::wallets_by_name(name)).await
     }
 
     /// Fetches the [`Motd`] from the Krist API
@@ -220,22 +463,54 @@ impl<M: ClientMarker> Client<M> {
     /// # Errors
     /// See [`Error`] for more info
     pub async fn get_motd(&self) -> Result<Motd, Error> {
-        self.krist_get("/api/krist/motd", None::<()>).await
+        self.call(endpoint::motd()).await
+    }
+
+    /// Estimates what sending `amount` will cost, for showing the total debit
+    /// before a transaction is confirmed.
+    ///
+    /// Kromer2 doesn't charge transaction fees or advertise a fee schedule, so
+    /// the fee is currently always zero. Going through this keeps callers
+    /// correct if that changes.
+    ///
+    /// # Errors
+    /// Errors with [`KristError::TransactionsDisabled`] if the server isn't
+    /// accepting transactions, or if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn estimate_cost(&self, amount: Decimal) -> Result<TransactionCost, Error> {
+        let motd = self.get_motd().await?;
+
+        if !motd.transactions_enabled {
+            return Err(KristError::TransactionsDisabled.into());
+        }
+
+        Ok(TransactionCost::new(amount, Decimal::ZERO))
     }
 
     /// Fetches a [`Wallet`] from the Krist API
     ///
     /// # Errors
-    /// Errors if `addr` does not exist or there is a network issue
+    /// Errors with [`KristError::AddrNotFound`] if `addr` does not exist, see
+    /// [`Self::get_wallet_addr_opt`] to get `None` instead. Also errors if
+    /// there is a network issue
     ///
     /// See [`Error`] for more info
     pub async fn get_wallet_addr(&self, addr: &Address) -> Result<Wallet, Error> {
-        let url = format!("/api/krist/addresses/{addr}");
-        Ok(self
-            .krist_get::<krist::GetAddrRes>(&url, None::<()>)
-            .await?
-            .address
-            .wallet)
+        self.call(endpoint::wallet(addr)).await
+    }
+
+    /// Fetches a [`Wallet`] from the Krist API, returning `None` if `addr`
+    /// does not exist. Like [`Self::get_transaction`], and like the empty
+    /// [`Vec`] returned by [`Self::get_wallet_uuid`] for a player without
+    /// wallets, this treats absence as a normal outcome rather than an error.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn get_wallet_addr_opt(&self, addr: &Address) -> Result<Option<Wallet>, Error> {
+        self.call(endpoint::wallet_opt(addr)).await
     }
 
     /// Fetches a [`Wallet`] from the Krist API as a `tuple` with the number of
@@ -246,13 +521,7 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn get_wallet_addr_wnames(&self, addr: &Address) -> Result<(Wallet, u32), Error> {
-        let url = format!("/api/krist/addresses/{addr}?fetchNames=true");
-        let res = self
-            .krist_get::<krist::GetAddrRes>(&url, None::<()>)
-            .await?
-            .address;
-
-        Ok((res.wallet, res.names))
+        self.call(endpoint::wallet_with_names(addr)).await
     }
 
     /// Fetches a [`WalletPage`] from the Krist API
@@ -262,7 +531,18 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn list_wallets(&self, page: Option<&Paginator>) -> Result<WalletPage, Error> {
-        self.krist_get("/api/krist/addresses", page).await
+        self.call(endpoint::wallets(page)).await
+    }
+
+    /// Streams every [`Wallet`] on the server, fetching one page of up to 1000
+    /// at a time. Use [`Self::list_wallets`] to fetch a single page instead.
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn wallet_stream(&self) -> impl Stream<Item = Result<Wallet, Error>> + Send + use<'_, M> {
+        paginate(move |page| async move { self.list_wallets(Some(&page)).await })
     }
 
     /// Fetches the richest wallets as a [`WalletPage`] from the Krist API
@@ -272,10 +552,49 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn list_rich(&self, page: Option<&Paginator>) -> Result<WalletPage, Error> {
-        self.krist_get("/api/krist/addresses/rich", page).await
+        self.call(endpoint::rich(page)).await
+    }
+
+    /// Gets the total number of wallets on the server. Only a single wallet is
+    /// fetched to read the total from.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn wallet_count(&self) -> Result<usize, Error> {
+        Ok(self.list_wallets(Some(&Paginator::new(0, 1))).await?.total)
     }
 
-    /// Fetches an address' recent transactions as a [`TransactionPage`]
+    /// Streams every [`Wallet`] with a balance above `min_balance`, so
+    /// `Decimal::ZERO` skips empty wallets.
+    ///
+    /// Wallets aren't listed in balance order, so this is a full scan of every
+    /// wallet on the server and can make many requests. If you only need the
+    /// largest wallets, use [`Self::list_rich`] instead.
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn active_wallets(
+        &self,
+        min_balance: Decimal,
+    ) -> impl Stream<Item = Result<Wallet, Error>> + Send + use<'_, M> {
+        paginate(move |page| async move { self.list_wallets(Some(&page)).await }).filter(
+            move |res| {
+                future::ready(
+                    res.as_ref()
+                        .map_or(true, |wallet| wallet.balance > min_balance),
+                )
+            },
+        )
+    }
+
+    /// Fetches an address' recent transactions as a [`TransactionPage`],
+    /// newest first. An offset of 0 starts at the newest transaction, and
+    /// higher offsets walk back in time. See [`Self::wallet_transactions`] to
+    /// list them oldest first.
     ///
     /// # Arguments
     /// * `addr` - The [`Address`] who's transactions you would like to list
@@ -291,12 +610,165 @@ impl<M: ClientMarker> Client<M> {
         mined: bool,
         page: Option<&Paginator>,
     ) -> Result<TransactionPage, Error> {
-        let url = format!(
-            "/api/krist/addresses/{}/transactions?excludeMined={}",
-            addr, !mined
-        );
+        self.call(endpoint::wallet_transactions(addr, mined, page))
+            .await
+    }
 
-        self.krist_get(&url, page).await
+    /// Fetches an address' transactions as a [`TransactionPage`] in the given
+    /// `order`. The offset of `page` counts from the start of that order.
+    ///
+    /// The server only lists an address' transactions newest first, so
+    /// [`Order::Ascending`] first fetches the total number of transactions and
+    /// then requests the matching page from the other end, reversing it. This
+    /// takes two requests, and a transaction made between them shifts the page
+    /// by one.
+    ///
+    /// # Arguments
+    /// * `addr` - The [`Address`] who's transactions you would like to list
+    /// * `order` - Whether to list the oldest or newest transactions first
+    /// * `mined` - Whether to include transactions of type "Mined" in the response
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist, or if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn wallet_transactions(
+        &self,
+        addr: &Address,
+        order: Order,
+        mined: bool,
+        page: Option<&Paginator>,
+    ) -> Result<TransactionPage, Error> {
+        if order == Order::Descending {
+            return self.recent_wallet_transactions(addr, mined, page).await;
+        }
+
+        let page = page.copied().unwrap_or_else(|| Paginator::new(0, 50));
+
+        let total = self
+            .recent_wallet_transactions(addr, mined, Some(&Paginator::new(0, 1)))
+            .await?
+            .total;
+
+        // The same transactions, counted from the newest
+        let end = total.saturating_sub(page.offset);
+        let start = end.saturating_sub(page.limit);
+
+        if start == end {
+            return Ok(TransactionPage {
+                count: 0,
+                total,
+                items: Vec::new(),
+            });
+        }
+
+        let mut res = self
+            .recent_wallet_transactions(addr, mined, Some(&Paginator::new(start, end - start)))
+            .await?;
+
+        res.items.reverse();
+
+        Ok(res)
+    }
+
+    /// Streams every [`Transaction`] involving `addr`, from newest to oldest.
+    /// Pages are fetched one at a time as the stream is polled, so memory use
+    /// stays bounded no matter how long the wallet's history is.
+    ///
+    /// # Arguments
+    /// * `addr` - The [`Address`] who's transactions you would like to stream
+    /// * `mined` - Whether to include transactions of type "Mined" in the stream
+    ///
+    /// # Errors
+    /// Yields an error and ends if `addr` does not exist, or if there is a
+    /// network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn all_wallet_transactions(
+        &self,
+        addr: &Address,
+        mined: bool,
+    ) -> impl Stream<Item = Result<Transaction, Error>> + Send + use<'_, M> {
+        let addr = *addr;
+
+        paginate(move |page| async move {
+            self.recent_wallet_transactions(&addr, mined, Some(&page))
+                .await
+        })
+    }
+
+    /// Totals `addr`'s transactions by [counterparty](Transaction::counterparty),
+    /// for seeing who a wallet trades with most. Mined transactions have no
+    /// counterparty and are skipped.
+    ///
+    /// Every transaction is fetched, so for wallets with a deep history pass a
+    /// `limit` to only count the most recent ones.
+    ///
+    /// Transactions with [`serverwelf`](Address::Serverwelf) mint and burn
+    /// Kromer rather than being peer to peer. Set `fold_serverwelf` to total
+    /// them in [`minted`](CounterpartySummary::minted) and
+    /// [`burned`](CounterpartySummary::burned) instead of as a counterparty.
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist, or if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn counterparty_summary(
+        &self,
+        addr: &Address,
+        limit: Option<usize>,
+        fold_serverwelf: bool,
+    ) -> Result<CounterpartySummary, Error> {
+        let txs = self
+            .all_wallet_transactions(addr, false)
+            .take(limit.unwrap_or(usize::MAX));
+        let mut txs = pin!(txs);
+
+        let mut summary = CounterpartySummary::default();
+
+        while let Some(tx) = txs.next().await {
+            summary.tally(addr, &tx?, fold_serverwelf);
+        }
+
+        Ok(summary)
+    }
+
+    /// Streams every [`Transaction`] made between `a` and `b`, in either
+    /// direction, from newest to oldest. This walks all of `a`'s transactions
+    /// and keeps those where `b` is the [counterparty](Transaction::counterparty),
+    /// so it can make many requests for busy wallets.
+    ///
+    /// ```rust
+    /// # use kromer_api::{Error, http::Client, model::Address};
+    /// # use futures_util::StreamExt;
+    /// # async fn run(client: Client<kromer_api::http::Basic>, a: Address, b: Address) -> Result<(), Error> {
+    /// let mut txs = std::pin::pin!(client.transactions_between_addresses(&a, &b));
+    ///
+    /// while let Some(tx) = txs.next().await {
+    ///     println!("{:#?}", tx?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Yields an error and ends if `a` does not exist or there is a network
+    /// issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn transactions_between_addresses(
+        &self,
+        a: &Address,
+        b: &Address,
+    ) -> impl Stream<Item = Result<Transaction, Error>> + Send + use<'_, M> {
+        let (a, b) = (*a, *b);
+
+        self.all_wallet_transactions(&a, false).filter(move |res| {
+            future::ready(
+                res.as_ref()
+                    .map_or(true, |tx| tx.counterparty(&a) == Some(b)),
+            )
+        })
     }
 
     /// Fetches the names owned by an address as a [`NamePage`]
@@ -310,9 +782,7 @@ impl<M: ClientMarker> Client<M> {
         addr: &Address,
         page: Option<&Paginator>,
     ) -> Result<NamePage, Error> {
-        let url = format!("/api/krist/addresses/{addr}/names");
-
-        self.krist_get(&url, page).await
+        self.call(endpoint::wallet_names(addr, page)).await
     }
 
     /// Checks if a [`PrivateKey`] corresponds with an address on the Kromer2
@@ -324,12 +794,29 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn login(&self, pk: &PrivateKey) -> Result<Address, Error> {
-        let body = AuthRequest { pk: pk.inner() };
+        self.call(endpoint::login(pk)).await
+    }
 
-        Ok(self
-            .krist_post::<AuthRes>("/api/krist/login", body)
-            .await?
-            .address)
+    /// Like [`Self::login`], but also returns whether the wallet existed before
+    /// logging in. `false` means a new wallet was created for `pk`.
+    ///
+    /// This looks the wallet up before logging in, which costs an extra
+    /// request and preforms the hashes to derive an [`Address`] from `pk`.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn login_checked(&self, pk: &PrivateKey) -> Result<(Address, bool), Error> {
+        let existed = match self.get_wallet_addr(&Address::from(pk)).await {
+            Ok(_) => true,
+            Err(Error::KristResponse {
+                source: KristError::AddrNotFound { .. },
+            }) => false,
+            Err(e) => return Err(e),
+        };
+
+        Ok((self.login(pk).await?, existed))
     }
 
     /// Checks the amount of Kromer in circulation
@@ -339,10 +826,26 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn supply(&self) -> Result<Decimal, Error> {
-        Ok(self
-            .krist_get::<SupplyRes>("/api/krist/supply", None::<()>)
-            .await?
-            .money_supply)
+        self.call(endpoint::supply()).await
+    }
+
+    /// Computes the fraction of the Kromer in circulation held by `addr`, as a
+    /// [`Decimal`] between `0` and `1`. The wallet and the supply are fetched
+    /// concurrently. If the server reports a supply of zero, the share is
+    /// zero.
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist or there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn supply_share(&self, addr: &Address) -> Result<Decimal, Error> {
+        let (wallet, supply) = future::try_join(self.get_wallet_addr(addr), self.supply()).await?;
+
+        Ok(wallet
+            .balance
+            .checked_div(supply)
+            .unwrap_or_default()
+            .clamp(Decimal::ZERO, Decimal::ONE))
     }
 
     /// Fetches [`NameInfo`] from the Krist API
@@ -352,9 +855,19 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn get_name(&self, name: &Name) -> Result<NameInfo, Error> {
-        let url = format!("/api/krist/names/{name}");
+        self.call(endpoint::name(name)).await
+    }
 
-        Ok(self.krist_get::<NameRes>(&url, None::<()>).await?.name)
+    /// Checks whether `name` is currently owned by `addr`. Useful as a
+    /// pre-flight check before transferring or updating a name, as it gives a
+    /// clearer answer than the server's `not_name_owner` error.
+    ///
+    /// # Errors
+    /// Errors if `name` does not exist or there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn owns_name(&self, name: &Name, addr: &Address) -> Result<bool, Error> {
+        Ok(self.get_name(name).await?.owner == *addr)
     }
 
     /// Fetches a [`NamePage`] from the Krist API
@@ -364,7 +877,52 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn list_names(&self, page: Option<&Paginator>) -> Result<NamePage, Error> {
-        self.krist_get("/api/krist/names", page).await
+        self.call(endpoint::names(page)).await
+    }
+
+    /// Streams every registered name, fetching one page of up to 1000 at a
+    /// time. Use [`Self::list_names`] to fetch a single page instead.
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn name_stream(&self) -> impl Stream<Item = Result<NameInfo, Error>> + Send + use<'_, M> {
+        paginate(move |page| async move { self.list_names(Some(&page)).await })
+    }
+
+    /// Gets the total number of names registered on the server. Only a single
+    /// name is fetched to read the total from.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn name_count_total(&self) -> Result<usize, Error> {
+        Ok(self.list_names(Some(&Paginator::new(0, 1))).await?.total)
+    }
+
+    /// Streams every name registered between `start` (inclusive) and `end`
+    /// (exclusive). Names are listed alphabetically rather than by
+    /// registration date, so this walks every name on the server and keeps
+    /// those whose [`registered`](NameInfo::registered) time falls in the
+    /// range.
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn names_registered_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<NameInfo, Error>> + Send + use<'_, M> {
+        paginate(move |page| async move { self.list_names(Some(&page)).await }).filter(move |res| {
+            future::ready(
+                res.as_ref()
+                    .map_or(true, |name| (start..end).contains(&name.registered)),
+            )
+        })
     }
 
     /// Gets the cost to buy a [`Name`] from the Krist API
@@ -374,40 +932,49 @@ impl<M: ClientMarker> Client<M> {
     ///
     /// See [`Error`] for more info
     pub async fn name_cost(&self) -> Result<Decimal, Error> {
-        Ok(self
-            .krist_get::<CostRes>("/api/krist/names/cost", None::<()>)
-            .await?
-            .name_cost)
+        self.call(endpoint::name_cost()).await
     }
 
-    /// Checks if a [`Name`] is available to buy
+    /// Gets the cost to buy a [`Name`] formatted for display with the
+    /// server's currency symbol, e.g. `"500.00 KRO"`. Falls back to
+    /// [`format_kromer`] if the server's [`Motd`] has no currency information.
     ///
     /// # Errors
     /// Errors if there is a network issue
     ///
     /// See [`Error`] for more info
-    pub async fn check_name(&self, name: &Name) -> Result<bool, Error> {
-        let url = format!("/api/krist/names/check/{name}");
+    pub async fn name_cost_formatted(&self) -> Result<String, Error> {
+        let (motd, cost) = future::try_join(self.get_motd(), self.name_cost()).await?;
 
-        Ok(self
-            .krist_get::<AvailRes>(&url, None::<()>)
-            .await?
-            .available)
+        Ok(motd
+            .currency
+            .map_or_else(|| format_kromer(cost), |currency| currency.format(cost)))
     }
 
-    /// Registers a [`Name`], will error if it fails
+    /// Checks if a [`Name`] is available to buy
     ///
     /// # Errors
-    /// Errors if the name is unavailable or the pk links to a wallet with
-    /// insufficient funds.
+    /// Errors if there is a network issue
     ///
     /// See [`Error`] for more info
-    pub async fn register_name(&self, name: &Name, pk: &PrivateKey) -> Result<(), Error> {
-        let url = format!("/api/krist/names/{name}");
-
-        let body = RegisterBody { privatekey: pk };
+    pub async fn check_name(&self, name: &Name) -> Result<bool, Error> {
+        self.call(endpoint::check_name(name)).await
+    }
 
-        self.krist_post::<()>(&url, body).await
+    /// Registers a [`Name`]. The name being taken and the wallet `pk` links
+    /// to having insufficient funds are the usual reasons registering fails,
+    /// so they are returned as a [`RegisterOutcome`] rather than an error.
+    ///
+    /// # Errors
+    /// Errors if `pk` is invalid or there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn register_name(
+        &self,
+        name: &Name,
+        pk: &PrivateKey,
+    ) -> Result<RegisterOutcome, Error> {
+        self.call(endpoint::register_name(name, pk)).await
     }
 
     /// Transfers a name to another address
@@ -428,14 +995,7 @@ impl<M: ClientMarker> Client<M> {
         addr: &Address,
         pk: &PrivateKey,
     ) -> Result<NameInfo, Error> {
-        let url = format!("/krist/api/names/{name}/transfer");
-
-        let body = TransferBody {
-            address: addr,
-            privatekey: pk,
-        };
-
-        Ok(self.krist_post::<NameRes>(&url, body).await?.name)
+        self.call(endpoint::transfer_name(name, addr, pk)).await
     }
 
     /// Updates a name, returning the updated [`NameInfo`]
@@ -456,18 +1016,35 @@ impl<M: ClientMarker> Client<M> {
         meta: Option<&str>,
         pk: &PrivateKey,
     ) -> Result<NameInfo, Error> {
-        let url = format!("/api/krist/names/{name}/update");
-
-        let body = UpdateBody {
-            privatekey: pk,
-            a: meta,
-        };
+        self.call(endpoint::update_name(name, meta, pk)).await
+    }
 
-        self.krist_post::<NameInfo>(&url, body).await
+    /// Lists every transaction on the server in the given `order` as a
+    /// [`TransactionPage`]. The offset of `page` counts from the start of that
+    /// order, so offset 0 is the oldest transaction when ascending and the
+    /// newest when descending.
+    ///
+    /// # Arguments
+    /// * `order` - Whether to list the oldest or newest transactions first
+    /// * `mined` - Whether to include transactions of type "Mined" in the response
+    /// * `page` -  The [`Paginator`] used in the query
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn transactions(
+        &self,
+        order: Order,
+        mined: bool,
+        page: Option<&Paginator>,
+    ) -> Result<TransactionPage, Error> {
+        self.call(endpoint::transactions(order, mined, page)).await
     }
 
     /// Lists transactions in order from oldest to newest as a
-    /// [`TransactionPage`]
+    /// [`TransactionPage`]. Shorthand for [`Self::transactions`] with
+    /// [`Order::Ascending`]
     ///
     /// # Arguments
     /// * `page` -  The [`Paginator`] used in the query
@@ -482,16 +1059,66 @@ impl<M: ClientMarker> Client<M> {
         mined: bool,
         page: Option<&Paginator>,
     ) -> Result<TransactionPage, Error> {
+        self.transactions(Order::Ascending, mined, page).await
+    }
+
+    /// Like [`Self::list_transactions`], but a transaction that fails to
+    /// deserialize doesn't fail the whole page. The transactions that parsed
+    /// are returned in order alongside an error for each that didn't, so
+    /// indexers can keep making progress against an imperfect server.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue or the page itself is malformed
+    ///
+    /// See [`Error`] for more info
+    pub async fn list_transactions_lenient(
+        &self,
+        mined: bool,
+        page: Option<&Paginator>,
+    ) -> Result<(Vec<Transaction>, Vec<ParseError>), Error> {
         let query = Some(ListTransactionsQuery {
-            exclude_mined: mined,
+            exclude_mined: !mined,
             page,
         });
 
-        self.krist_get("/api/krist/transactions", query).await
+        let raw: Page<serde_json::Value> = self.krist_get("/api/krist/transactions", query).await?;
+
+        let mut txs = Vec::with_capacity(raw.items.len());
+        let mut errors = Vec::new();
+
+        decimal::with_precision(self.decimal_places, || {
+            for (index, value) in raw.items.into_iter().enumerate() {
+                match serde_json::from_value(value) {
+                    Ok(tx) => txs.push(tx),
+                    Err(source) => errors.push(ParseError::MalformedTransaction { index, source }),
+                }
+            }
+        });
+
+        Ok((txs, errors))
+    }
+
+    /// Streams every transaction on the server from oldest to newest, fetching
+    /// one page of up to 1000 at a time. Use [`Self::list_transactions`] to
+    /// fetch a single page instead.
+    ///
+    /// # Arguments
+    /// * `mined` - Whether to include transactions of type "Mined" in the stream
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn transaction_stream(
+        &self,
+        mined: bool,
+    ) -> impl Stream<Item = Result<Transaction, Error>> + Send + use<'_, M> {
+        paginate(move |page| async move { self.list_transactions(mined, Some(&page)).await })
     }
 
     /// Lists transactions in order from newest to oldest as a
-    /// [`TransactionPage`]
+    /// [`TransactionPage`]. Shorthand for [`Self::transactions`] with
+    /// [`Order::Descending`]
     ///
     /// # Arguments
     /// * `page` -  The [`Paginator`] used in the query
@@ -506,34 +1133,194 @@ impl<M: ClientMarker> Client<M> {
         mined: bool,
         page: Option<&Paginator>,
     ) -> Result<TransactionPage, Error> {
-        let query = Some(ListTransactionsQuery {
-            exclude_mined: mined,
-            page,
-        });
+        self.transactions(Order::Descending, mined, page).await
+    }
 
-        self.krist_get("/api/krist/transactions/latest", query)
-            .await
+    /// Polls the server every `interval` for new transactions, yielding each
+    /// one once in the order they were made. This is a live feed for when
+    /// websockets aren't available, such as behind restrictive proxies.
+    ///
+    /// Transaction ids only ever increase, so the highest id yielded so far
+    /// acts as a watermark. Pass the id of the last transaction you processed
+    /// as `after` to resume from it after a restart without replaying
+    /// anything, or `None` to only yield transactions made after the stream is
+    /// first polled.
+    ///
+    /// New transactions shift the offsets of pages a poll hasn't fetched yet,
+    /// so one transaction can land on two pages. The stream is wrapped in a
+    /// [`DedupStream`](crate::stream::DedupStream) so it is still only yielded
+    /// once.
+    ///
+    /// A transaction takes up to `interval` to show up, so shorter intervals
+    /// lower latency at the cost of more requests. If a poll fails the error is
+    /// yielded and polling carries on after the next interval.
+    ///
+    /// # Errors
+    /// Yields an error if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    #[cfg(feature = "tokio")]
+    pub fn poll_new_transactions(
+        &self,
+        after: Option<u32>,
+        interval: std::time::Duration,
+    ) -> impl Stream<Item = Result<Transaction, Error>> + Send + use<'_, M> {
+        let state = (after, std::collections::VecDeque::new(), false);
+
+        let feed = stream::unfold(
+            state,
+            move |(mut watermark, mut buf, mut polled)| async move {
+                loop {
+                    if let Some(tx) = buf.pop_front() {
+                        return Some((Ok(tx), (watermark, buf, polled)));
+                    }
+
+                    if polled {
+                        self.clock.sleep(interval).await;
+                    }
+                    polled = true;
+
+                    match self.transactions_after(watermark).await {
+                        Ok(txs) => {
+                            let newest = txs.last().map(|tx: &Transaction| tx.id);
+
+                            // Without a watermark, this poll only sets one
+                            if watermark.is_some() {
+                                buf.extend(txs);
+                            }
+
+                            watermark = newest.max(watermark);
+                        }
+                        Err(e) => return Some((Err(e), (watermark, buf, polled))),
+                    }
+                }
+            },
+        );
+
+        // Repeats only happen within a single poll, so this only needs to
+        // cover a few pages
+        crate::stream::DedupStream::new(feed, 1024)
     }
 
-    /// Gets a specific [`Transaction`] by `id`. Will return None if the
-    /// transaction does not exist
+    /// Fetches every transaction with an id above `watermark`, oldest first.
+    /// With no watermark, only the newest transaction is fetched.
+    #[cfg(feature = "tokio")]
+    async fn transactions_after(&self, watermark: Option<u32>) -> Result<Vec<Transaction>, Error> {
+        let limit = if watermark.is_some() { 100 } else { 1 };
+        let mut page = Paginator::new(0, limit);
+        let mut res = Vec::new();
+
+        loop {
+            let txs = self
+                .transactions(Order::Descending, true, Some(&page))
+                .await?
+                .items;
+
+            let full = txs.len() == limit;
+            let before = res.len();
+
+            res.extend(
+                txs.into_iter()
+                    .take_while(|tx| watermark.is_none_or(|w| tx.id > w)),
+            );
+
+            if watermark.is_none() || !full || res.len() - before < limit {
+                break;
+            }
+
+            page.next_page();
+        }
+
+        res.reverse();
+
+        Ok(res)
+    }
+
+    /// Gets the total number of transactions made on the server, including
+    /// mined ones. Only a single transaction is fetched to read the total from.
     ///
     /// # Errors
     /// Errors if there is a network issue
     ///
     /// See [`Error`] for more info
-    pub async fn get_transaction(&self, id: u32) -> Result<Option<Transaction>, Error> {
-        let url = format!("/api/krist/transactions/{id}");
+    pub async fn transaction_count(&self) -> Result<usize, Error> {
+        Ok(self
+            .transactions(Order::Descending, true, Some(&Paginator::new(0, 1)))
+            .await?
+            .total)
+    }
 
-        let res = self.krist_get::<TransactionRes>(&url, None::<()>).await;
+    /// Streams every [`Transaction`] involving `name`, from newest to oldest.
+    /// This includes its purchase, transfers, and data changes, as well as
+    /// payments sent to it.
+    ///
+    /// Kromer2 has no endpoint for this, so it is best-effort: the stream walks
+    /// the entire transaction history and keeps those whose
+    /// [`name`](Transaction::name) or [`sent_name`](Transaction::sent_name)
+    /// matches, which makes many requests.
+    ///
+    /// # Errors
+    /// Yields an error and ends if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn name_transactions(
+        &self,
+        name: &Name,
+    ) -> impl Stream<Item = Result<Transaction, Error>> + Send + use<'_, M> {
+        let name = name.clone();
+        let name_str = name.to_string();
+
+        paginate(move |page| async move { self.new_transactions(false, Some(&page)).await }).filter(
+            move |res| {
+                future::ready(res.as_ref().map_or(true, |tx| {
+                    tx.name.as_deref() == Some(name_str.as_str())
+                        || tx.sent_name.as_ref() == Some(&name)
+                }))
+            },
+        )
+    }
 
-        match res {
-            Ok(tr) => Ok(Some(tr.transaction)),
-            Err(Error::KristResponse {
-                source: KristError::TransactionNotFound,
-            }) => Ok(None),
-            Err(e) => Err(e),
+    /// Finds the [`NamePurchase`](TransactionType::NamePurchase) transaction
+    /// that registered `name`, for showing where a name came from. Returns
+    /// `None` if it can't be found, e.g. because it was pruned.
+    ///
+    /// This walks [`Self::name_transactions`] back to the name's
+    /// [`registered`](NameInfo::registered) time, so it makes many requests for
+    /// old names.
+    ///
+    /// # Errors
+    /// Errors if `name` does not exist or there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn name_registration_tx(&self, name: &Name) -> Result<Option<Transaction>, Error> {
+        // Allows for the transaction and the name being timestamped separately
+        let cutoff = self.get_name(name).await?.registered - TimeDelta::minutes(1);
+
+        let mut txs = pin!(
+            self.name_transactions(name)
+                .take_while(|res| future::ready(res.as_ref().map_or(true, |tx| tx.time >= cutoff)))
+        );
+
+        while let Some(tx) = txs.next().await {
+            let tx = tx?;
+
+            if matches!(tx.transaction_type, TransactionType::NamePurchase) {
+                return Ok(Some(tx));
+            }
         }
+
+        Ok(None)
+    }
+
+    /// Gets a specific [`Transaction`] by `id`. Will return None if the
+    /// transaction does not exist
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn get_transaction(&self, id: u32) -> Result<Option<Transaction>, Error> {
+        self.call(endpoint::transaction(id)).await
     }
 
     /// Makes a Kromer [`Transaction`]. Note that this does preform several
@@ -543,7 +1330,9 @@ impl<M: ClientMarker> Client<M> {
     /// # Arguments
     /// * `addr` - The [`Address`] the transaction is going to
     /// * `amount` - The amount of Kromer to send
-    /// * `meta` - The metadata to attach to this transaction
+    /// * `meta` - The metadata to attach to this transaction. If the client was
+    ///   built with [`ClientBuilder::tag_useragent`], a `useragent` entry is
+    ///   appended to it unless it already has one.
     /// * `pk` - The [`PrivateKey`] attached to the wallet sending the transaction
     ///
     /// # Errors
@@ -558,20 +1347,482 @@ impl<M: ClientMarker> Client<M> {
         meta: Option<&str>,
         pk: &PrivateKey,
     ) -> Result<Transaction, Error> {
-        let pk_addr = Address::from(pk);
+        let prepared = endpoint::prepare_transaction(self.tag_useragent, addr, amount, meta, pk)?;
+
+        self.submit(&prepared).await
+    }
 
-        ensure!(pk_addr != *addr, SameWalletTransferSnafu);
+    /// Submits a [`PreparedTransaction`], which may have been prepared on
+    /// another machine. The same wallet check was already done when it was
+    /// prepared, so unlike [`Self::make_transaction`] no hashing happens here.
+    ///
+    /// # Errors
+    /// Errors if the sending wallet has insufficient funds or there is a
+    /// network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn submit(&self, prepared: &PreparedTransaction) -> Result<Transaction, Error> {
+        self.call(endpoint::submit(prepared)).await
+    }
 
-        let body = MakeTransactionBody {
-            privatekey: pk,
-            metadata: meta,
-            to: addr,
-            amount,
+    /// Submits a [`PreparedTransaction`] that was serialized as `JSON`, such as
+    /// one read back from a durable queue by a worker. Preparing and
+    /// submitting can then happen at different times, with the queue handling
+    /// retries.
+    ///
+    /// Like [`Self::submit`], the same wallet check isn't re-run here, as it
+    /// was done when the transaction was prepared. Only submit bodies that
+    /// were produced by serializing a [`PreparedTransaction`].
+    ///
+    /// ```no_run
+    /// # use kromer_api::{Error, http::Client, model::{Address, PrivateKey, krist::PreparedTransaction}};
+    /// # use rust_decimal::Decimal;
+    /// # async fn run(client: Client<kromer_api::http::Basic>, to: Address, pk: PrivateKey) -> Result<(), Error> {
+    /// let prepared = PreparedTransaction::new(&to, Decimal::ONE, None, pk)?;
+    /// let queued = serde_json::to_string(&prepared).unwrap();
+    ///
+    /// // Later, on a worker
+    /// let tx = client.submit_serialized(&queued).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Errors with [`Error::BadPreparedTransaction`] if `body` can't be parsed,
+    /// or if the sending wallet has insufficient funds or there is a network
+    /// issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn submit_serialized(&self, body: &str) -> Result<Transaction, Error> {
+        let prepared: PreparedTransaction =
+            serde_json::from_str(body).context(BadPreparedTransactionSnafu)?;
+
+        self.submit(&prepared).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientBuilder;
+    use crate::{
+        metrics::Metrics,
+        mock::{self, MOTD, MockServer},
+    };
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        started: AtomicUsize,
+        finished: AtomicUsize,
+    }
+
+    #[derive(Debug)]
+    struct Shared(Arc<Counts>);
+
+    impl Metrics for Shared {
+        fn request_started(&self, _path: &str) {
+            self.0.started.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn request_finished(&self, _path: &str, status: Option<u16>, _latency: Duration) {
+            assert_eq!(status, None);
+            self.0.finished.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn debug_shows_url_and_marker() {
+        let client = ClientBuilder::new().build("https://kromer.test").unwrap();
+
+        let debug = format!("{client:?}");
+        assert!(debug.starts_with(r#"Client<Basic> { url: "https://kromer.test/""#));
+    }
+
+    #[tokio::test]
+    async fn from_parts() {
+        let server = MockServer::respond("200 OK", MOTD);
+
+        let http = reqwest::Client::builder()
+            .user_agent("my-app/1.0")
+            .build()
+            .unwrap();
+        let client = super::Client::from_parts(&server.url, http).unwrap();
+
+        assert_eq!(client.get_motd().await.unwrap().msg, "hi");
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let server = MockServer::silent();
+
+        let client = ClientBuilder::new()
+            .timeout(Duration::from_millis(1))
+            .user_agent("my-bot/1.0")
+            .build(&server.url)
+            .unwrap();
+
+        assert!(matches!(
+            client.get_motd().await,
+            Err(crate::Error::RequestFailed { .. })
+        ));
+
+        let res = ClientBuilder::new()
+            .user_agent("bad\nagent")
+            .build("http://localhost");
+        assert!(matches!(res, Err(crate::Error::BadUserAgent { .. })));
+    }
+
+    #[tokio::test]
+    async fn dropping_request_reports_finished() {
+        // Requests hang until dropped
+        let server = MockServer::silent();
+
+        let counts = Arc::new(Counts::default());
+        let client = ClientBuilder::new()
+            .metrics(Shared(counts.clone()))
+            .build(&server.url)
+            .unwrap();
+
+        let res = tokio::time::timeout(Duration::from_millis(100), client.get_motd()).await;
+        assert!(res.is_err());
+
+        assert_eq!(counts.started.load(Ordering::Relaxed), 1);
+        assert_eq!(counts.finished.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn response_too_large() {
+        // Without a declared length, so the limit is hit while reading
+        let server = MockServer::new(|_| {
+            let body = format!(r#"{{"motd":"{}"}}"#, "a".repeat(64));
+            Some(format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{body}"
+            ))
+        });
+
+        let client = ClientBuilder::new()
+            .max_response_bytes(16)
+            .build(&server.url)
+            .unwrap();
+
+        assert!(matches!(
+            client.get_motd().await,
+            Err(crate::Error::ResponseTooLarge { limit: 16, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn lenient_transaction_page() {
+        let txs = [
+            mock::transaction(1, "kbbbbbbbbb"),
+            mock::transaction(2, "not an address"),
+            mock::transaction(3, "kbbbbbbbbb"),
+        ];
+        let server = MockServer::respond("200 OK", mock::transaction_page(&txs, 3));
+
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        let (txs, errors) = client.list_transactions_lenient(false, None).await.unwrap();
+        assert_eq!(txs.iter().map(|tx| tx.id).collect::<Vec<_>>(), [1, 3]);
+        assert!(matches!(
+            errors[..],
+            [crate::model::ParseError::MalformedTransaction { index: 1, .. }]
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        use super::RetryPolicy;
+
+        let unavailable = "503 Service Unavailable";
+        let server = MockServer::seq(vec![
+            (unavailable, String::new()),
+            ("502 Bad Gateway", String::new()),
+            ("200 OK", MOTD.to_string()),
+        ]);
+
+        let policy = RetryPolicy::new().base_delay(Duration::from_millis(1));
+        let client = ClientBuilder::new()
+            .retry(policy)
+            .build(&server.url)
+            .unwrap();
+
+        assert_eq!(client.get_motd().await.unwrap().msg, "hi");
+        assert_eq!(server.hits(), 3);
+
+        // POSTs are sent once, whatever the policy
+        let server = MockServer::respond(unavailable, "");
+        let client = ClientBuilder::new()
+            .retry(policy)
+            .build(&server.url)
+            .unwrap();
+
+        let pk = crate::model::PrivateKey::new("key");
+        let to: crate::model::Address = "kaaaaaaaaa".parse().unwrap();
+        assert!(
+            client
+                .make_transaction(&to, rust_decimal::Decimal::ONE, None, &pk)
+                .await
+                .is_err()
+        );
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn retry_delay_runs_on_clock() {
+        use super::RetryPolicy;
+        use crate::clock::MockClock;
+        use chrono::{DateTime, TimeDelta};
+
+        let server = MockServer::seq(vec![
+            ("503 Service Unavailable", String::new()),
+            ("200 OK", MOTD.to_string()),
+        ]);
+
+        let clock = MockClock::new(DateTime::UNIX_EPOCH);
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_hours(1))
+            .jitter(false);
+        let client = ClientBuilder::new()
+            .retry(policy)
+            .clock(clock.clone())
+            .build(&server.url)
+            .unwrap();
+
+        let req = tokio::spawn(async move { client.get_motd().await });
+
+        while server.hits() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!req.is_finished());
+
+        clock.advance(TimeDelta::hours(1));
+
+        assert_eq!(req.await.unwrap().unwrap().msg, "hi");
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn error_keeps_http_status() {
+        use crate::{Error, model::krist::KristError};
+
+        let body = r#"{"ok":false,"error":"rate_limit_hit","message":"Rate limit hit"}"#;
+        let server = MockServer::respond("429 Too Many Requests", body);
+
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        assert!(matches!(
+            client.get_motd().await,
+            Err(Error::KristResponse {
+                source: KristError::InternalServerError {
+                    status: Some(429),
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn register_outcomes() {
+        use crate::model::{
+            PrivateKey,
+            krist::{Name, RegisterOutcome},
         };
 
-        Ok(self
-            .krist_post::<TransactionRes>("/api/krist/transactions", body)
-            .await?
-            .transaction)
+        let name = Name::parse("example").unwrap();
+        let pk = PrivateKey::new("key");
+
+        let cases = [
+            ("200 OK", r#"{"ok":true}"#, RegisterOutcome::Registered),
+            (
+                "409 Conflict",
+                r#"{"ok":false,"error":"name_taken","message":"Name example is already taken"}"#,
+                RegisterOutcome::NameTaken,
+            ),
+            (
+                "403 Forbidden",
+                r#"{"ok":false,"error":"insufficient_balance","message":"Insufficient balance"}"#,
+                RegisterOutcome::InsufficientFunds,
+            ),
+        ];
+
+        for (status, body, outcome) in cases {
+            let server = MockServer::respond(status, body);
+            let client = ClientBuilder::new().build(&server.url).unwrap();
+
+            assert_eq!(client.register_name(&name, &pk).await.unwrap(), outcome);
+        }
+    }
+
+    /// Serves 5 transactions with ids 1 to 5, oldest first from
+    /// `/transactions` and newest first from every other endpoint, honoring
+    /// `offset` and `limit`. Like some servers, pages never hold more than 3
+    /// transactions whatever the limit.
+    fn transaction_server() -> MockServer {
+        MockServer::new(|target| {
+            let param = |name: &str| {
+                target
+                    .split(['?', '&'])
+                    .find_map(|kv| kv.strip_prefix(name)?.strip_prefix('='))
+                    .map_or(0, |v| v.parse::<usize>().unwrap())
+            };
+
+            let mut ids: Vec<u32> = (1..=5).collect();
+            if !target.starts_with("/api/krist/transactions?") {
+                ids.reverse();
+            }
+
+            let txs: Vec<_> = ids
+                .into_iter()
+                .skip(param("offset"))
+                .take(param("limit").min(3))
+                .map(|id| mock::transaction(id, "kbbbbbbbbb"))
+                .collect();
+
+            Some(mock::json("200 OK", &mock::transaction_page(&txs, 5)))
+        })
+    }
+
+    #[tokio::test]
+    async fn streams_partial_pages() {
+        use futures_util::StreamExt;
+
+        let server = transaction_server();
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        let ids: Vec<_> = client
+            .transaction_stream(false)
+            .map(|tx| tx.unwrap().id)
+            .collect()
+            .await;
+
+        assert_eq!(ids, [1, 2, 3, 4, 5]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn poll_from_watermark() {
+        use futures_util::StreamExt;
+
+        let server = transaction_server();
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        let ids: Vec<_> = client
+            .poll_new_transactions(Some(2), Duration::from_millis(10))
+            .take(3)
+            .map(|tx| tx.unwrap().id)
+            .collect()
+            .await;
+
+        assert_eq!(ids, [3, 4, 5]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn poll_skips_shifted_pages() {
+        use futures_util::StreamExt;
+
+        // A transaction made between fetching the two pages pushes 101 from
+        // the end of the first page to the start of the second
+        let server = MockServer::new(|target| {
+            let ids: Vec<u32> = if target.contains("offset=100") {
+                (2..=101).rev().collect()
+            } else {
+                (101..=200).rev().collect()
+            };
+
+            let txs: Vec<_> = ids
+                .into_iter()
+                .map(|id| mock::transaction(id, "kbbbbbbbbb"))
+                .collect();
+
+            Some(mock::json("200 OK", &mock::transaction_page(&txs, 200)))
+        });
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        let ids: Vec<_> = client
+            .poll_new_transactions(Some(2), Duration::from_millis(10))
+            .take(198)
+            .map(|tx| tx.unwrap().id)
+            .collect()
+            .await;
+
+        assert_eq!(ids, (3..=200).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn transfer_name_target() {
+        let server = MockServer::respond("200 OK", mock::name("example", "kbbbbbbbbb"));
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        let name = "example".parse().unwrap();
+        let to = "kbbbbbbbbb".parse().unwrap();
+        let pk = crate::model::PrivateKey::new("key");
+
+        let info = client.transfer_name(&name, &to, &pk).await.unwrap();
+        assert_eq!(info.owner, to);
+        assert_eq!(server.requests(), ["/api/krist/names/example/transfer"]);
+    }
+
+    #[tokio::test]
+    async fn mined_flag_sets_exclude_mined() {
+        let server = MockServer::respond("200 OK", mock::transaction_page(&[], 0));
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+
+        client.list_transactions(true, None).await.unwrap();
+        client.new_transactions(false, None).await.unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [
+                "/api/krist/transactions?excludeMined=false",
+                "/api/krist/transactions/latest?excludeMined=true",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_order() {
+        use super::{Order, Paginator, TransactionPage};
+        use crate::model::Address;
+
+        let server = transaction_server();
+        let client = ClientBuilder::new().build(&server.url).unwrap();
+        let addr = Address::parse(b"kbbbbbbbbb").unwrap();
+
+        let ids =
+            |page: TransactionPage| -> Vec<u32> { page.items.iter().map(|tx| tx.id).collect() };
+
+        for (order, offset, expected) in [
+            (Order::Descending, 0, vec![5, 4]),
+            (Order::Descending, 2, vec![3, 2]),
+            (Order::Ascending, 0, vec![1, 2]),
+            (Order::Ascending, 2, vec![3, 4]),
+            (Order::Ascending, 4, vec![5]),
+            (Order::Ascending, 6, vec![]),
+        ] {
+            let page = Paginator::new(offset, 2);
+
+            let wallet = client
+                .wallet_transactions(&addr, order, false, Some(&page))
+                .await
+                .unwrap();
+            assert_eq!(ids(wallet), expected, "{order:?} wallet at {offset}");
+
+            let all = client
+                .transactions(order, false, Some(&page))
+                .await
+                .unwrap();
+            assert_eq!(ids(all), expected, "{order:?} global at {offset}");
+        }
     }
 }
@@ -0,0 +1,542 @@
+//! A synchronous client for Kromer2's HTTP API, for programs without an async
+//! runtime. Enabled with the `blocking` feature.
+//!
+//! [`Client`] covers the same ground as the async
+//! [`Client`](super::Client) for fetching the motd, wallets, names and
+//! transactions, and sending transactions, returning the same models and
+//! [`Error`]s. Helpers built on streams or concurrent requests are left to
+//! the async client.
+//!
+//! ```no_run
+//! # use kromer_api::{Error, http::blocking::Client};
+//! # fn run() -> Result<(), Error> {
+//! let client = Client::new("https://kromer.reconnected.cc")?;
+//! let motd = client.get_motd()?;
+//!
+//! println!("{}", motd.msg);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Panics
+//! Like [`reqwest::blocking`], which it is built on, a blocking client must
+//! not be created, used or dropped from within an async runtime, as doing so
+//! panics.
+
+use super::{
+    ClientBuilder, IntoUrl, Order, Paginator,
+    endpoint::{self, Api, Endpoint},
+    krist::{self, RawKristError},
+};
+use crate::{
+    BadRequestSnafu, Error, RequestFailedSnafu, ResponseTooLargeSnafu,
+    model::{
+        Address, PrivateKey, Wallet,
+        krist::{
+            Motd, Name, NameInfo, NamePage, PreparedTransaction, RegisterOutcome, Transaction,
+            TransactionPage, WalletPage,
+        },
+    },
+};
+use reqwest::blocking::{Request, Response};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+use std::io::{self, Write};
+use tracing::{trace, warn};
+use url::Url;
+use uuid::Uuid;
+
+/// A blocking HTTP client for calling the Kromer2 API. Reuses connections and
+/// parses responses into the same types as the async
+/// [`Client`](super::Client).
+///
+/// The retry policy, circuit breaker, clock and metrics hook of a
+/// [`ClientBuilder`] only apply to the async client, and are ignored by
+/// [`ClientBuilder::build_blocking`].
+pub struct Client {
+    url: Url,
+    http: reqwest::blocking::Client,
+    max_response_bytes: usize,
+    tag_useragent: bool,
+    decimal_places: Option<u32>,
+}
+
+/// Shows the server, leaving out the connection pool and other internals
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("url", &self.url.as_str())
+            .field("decimal_places", &self.decimal_places)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Collects a response body, refusing to grow past `limit` bytes
+struct Limited {
+    body: Vec<u8>,
+    limit: usize,
+    exceeded: bool,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.body.len() + buf.len() > self.limit {
+            self.exceeded = true;
+            return Err(io::ErrorKind::FileTooLarge.into());
+        }
+
+        self.body.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Create a new blocking client for the Kromer2 API. Use a
+    /// [`ClientBuilder`] if you need to change any of its settings.
+    ///
+    /// # Errors
+    /// Errors if `url` is not a valid [`Url`]
+    ///
+    /// See [`Error`] for more info
+    ///
+    /// # Panics
+    /// Panics if we cannot construct the client for an unknown reason, or if
+    /// called from within an async runtime
+    pub fn new(url: impl IntoUrl) -> Result<Self, Error> {
+        ClientBuilder::new().build_blocking(url)
+    }
+
+    pub(super) const fn from_parts(
+        url: Url,
+        http: reqwest::blocking::Client,
+        max_response_bytes: usize,
+        tag_useragent: bool,
+        decimal_places: Option<u32>,
+    ) -> Self {
+        Self {
+            url,
+            http,
+            max_response_bytes,
+            tag_useragent,
+            decimal_places,
+        }
+    }
+
+    fn query(&self, req: Request) -> Result<Response, Error> {
+        trace!("sending a {} request to {}", req.method(), req.url());
+
+        let response = self.http.execute(req).context(RequestFailedSnafu)?;
+        let status = response.status();
+
+        if !status.is_success() {
+            warn!("got HTTP code {} from {}", status, response.url());
+        }
+
+        Ok(response)
+    }
+
+    /// Reads the body of `response` as `JSON`, giving up once it grows past
+    /// the client's [`max_response_bytes`](ClientBuilder::max_response_bytes)
+    fn read_json<T>(&self, mut response: Response) -> Result<T, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let limit = self.max_response_bytes;
+        endpoint::check_declared_len(response.content_length(), limit)?;
+
+        let mut body = Limited {
+            body: Vec::new(),
+            limit,
+            exceeded: false,
+        };
+
+        let res = response.copy_to(&mut body);
+        ensure!(!body.exceeded, ResponseTooLargeSnafu { limit });
+        res.context(RequestFailedSnafu)?;
+
+        endpoint::parse_json(&body.body, self.decimal_places)
+    }
+
+    /// Sends the request described by `endpoint`
+    fn call<R, T, Q, B>(&self, endpoint: Endpoint<R, T, Q, B>) -> Result<T, Error>
+    where
+        R: for<'de> Deserialize<'de>,
+        Q: Serialize,
+        B: Serialize,
+    {
+        let url = endpoint.url(&self.url);
+        let Endpoint {
+            api,
+            method,
+            query,
+            body,
+            finish,
+            ..
+        } = endpoint;
+
+        let res = (|| {
+            let mut req = self.http.request(method, url?);
+
+            if let Some(query) = &query {
+                req = req.query(query);
+            }
+
+            if let Some(body) = &body {
+                req = req.json(body);
+            }
+
+            let response = self.query(req.build().context(BadRequestSnafu)?)?;
+
+            let status = response.status();
+
+            if api == Api::Krist && !status.is_success() {
+                let raw = self.read_json::<RawKristError>(response);
+
+                return Err(krist::response_error(raw, status));
+            }
+
+            self.read_json::<R>(response)
+        })();
+
+        finish(res)
+    }
+
+    /// Fetches the [`Motd`] from the Krist API
+    ///
+    /// # Errors
+    /// See [`Error`] for more info
+    pub fn get_motd(&self) -> Result<Motd, Error> {
+        self.call(endpoint::motd())
+    }
+
+    /// Fetches all [`Wallets`](Wallet) attached to a `Minecraft` `UUID`
+    /// # Errors
+    /// Errors if there is no user with a `UUID` of `id` found by Kromer2, or
+    /// there is some other network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn get_wallet_uuid(&self, id: &Uuid) -> Result<Vec<Wallet>, Error> {
+        self.call(endpoint::wallets_by_uuid(id))
+    }
+
+    /// Fetches all [`Wallets`](Wallet) attached to a `Minecraft` `username`
+    /// # Errors
+    /// Errors if there is no user with `name` found by Kromer2, or there is
+    /// some other network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn get_wallet_name(&self, name: &str) -> Result<Vec<Wallet>, Error> {
+        self.call(endpoint::wallets_by_name(name))
+    }
+
+    /// Fetches a [`Wallet`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors with [`KristError::AddrNotFound`] if `addr` does not exist, see
+    /// [`Self::get_wallet_addr_opt`] to get `None` instead. Also errors if
+    /// there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn get_wallet_addr(&self, addr: &Address) -> Result<Wallet, Error> {
+        self.call(endpoint::wallet(addr))
+    }
+
+    /// Fetches a [`Wallet`] from the Krist API, returning `None` if `addr`
+    /// does not exist
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn get_wallet_addr_opt(&self, addr: &Address) -> Result<Option<Wallet>, Error> {
+        self.call(endpoint::wallet_opt(addr))
+    }
+
+    /// Fetches a [`Wallet`] from the Krist API as a `tuple` with the number of
+    /// names that wallet owns
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist or there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn get_wallet_addr_wnames(&self, addr: &Address) -> Result<(Wallet, u32), Error> {
+        self.call(endpoint::wallet_with_names(addr))
+    }
+
+    /// Fetches a [`WalletPage`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn list_wallets(&self, page: Option<&Paginator>) -> Result<WalletPage, Error> {
+        self.call(endpoint::wallets(page))
+    }
+
+    /// Fetches the richest wallets as a [`WalletPage`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn list_rich(&self, page: Option<&Paginator>) -> Result<WalletPage, Error> {
+        self.call(endpoint::rich(page))
+    }
+
+    /// Fetches an address' most recent transactions as a [`TransactionPage`],
+    /// newest first
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist, or if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn recent_wallet_transactions(
+        &self,
+        addr: &Address,
+        mined: bool,
+        page: Option<&Paginator>,
+    ) -> Result<TransactionPage, Error> {
+        self.call(endpoint::wallet_transactions(addr, mined, page))
+    }
+
+    /// Fetches the names owned by an address as a [`NamePage`]
+    ///
+    /// # Errors
+    /// Errors if `addr` does not exist or there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn list_wallet_names(
+        &self,
+        addr: &Address,
+        page: Option<&Paginator>,
+    ) -> Result<NamePage, Error> {
+        self.call(endpoint::wallet_names(addr, page))
+    }
+
+    /// Checks if a [`PrivateKey`] corresponds with an address on the Kromer2
+    /// server, if it does it returns the address, if not it creates a new
+    /// address and returns it.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn login(&self, pk: &PrivateKey) -> Result<Address, Error> {
+        self.call(endpoint::login(pk))
+    }
+
+    /// Checks the amount of Kromer in circulation
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn supply(&self) -> Result<Decimal, Error> {
+        self.call(endpoint::supply())
+    }
+
+    /// Fetches [`NameInfo`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn get_name(&self, name: &Name) -> Result<NameInfo, Error> {
+        self.call(endpoint::name(name))
+    }
+
+    /// Fetches a [`NamePage`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn list_names(&self, page: Option<&Paginator>) -> Result<NamePage, Error> {
+        self.call(endpoint::names(page))
+    }
+
+    /// Gets the cost to buy a [`Name`] from the Krist API
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn name_cost(&self) -> Result<Decimal, Error> {
+        self.call(endpoint::name_cost())
+    }
+
+    /// Checks if a [`Name`] is available to buy
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn check_name(&self, name: &Name) -> Result<bool, Error> {
+        self.call(endpoint::check_name(name))
+    }
+
+    /// Registers a [`Name`], see
+    /// [`Client::register_name`](super::Client::register_name)
+    ///
+    /// # Errors
+    /// Errors if `pk` is invalid or there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn register_name(&self, name: &Name, pk: &PrivateKey) -> Result<RegisterOutcome, Error> {
+        self.call(endpoint::register_name(name, pk))
+    }
+
+    /// Transfers `name` to `addr`, see
+    /// [`Client::transfer_name`](super::Client::transfer_name)
+    ///
+    /// # Errors
+    /// Will error if `name` does not belong to the address pointed to by `pk`,
+    /// or if there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn transfer_name(
+        &self,
+        name: &Name,
+        addr: &Address,
+        pk: &PrivateKey,
+    ) -> Result<NameInfo, Error> {
+        self.call(endpoint::transfer_name(name, addr, pk))
+    }
+
+    /// Updates a name, see
+    /// [`Client::update_name`](super::Client::update_name)
+    ///
+    /// # Errors
+    /// Will error does not exist or belong to `pk`, or if there is a network
+    /// issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn update_name(
+        &self,
+        name: &Name,
+        meta: Option<&str>,
+        pk: &PrivateKey,
+    ) -> Result<NameInfo, Error> {
+        self.call(endpoint::update_name(name, meta, pk))
+    }
+
+    /// Lists every transaction on the server in the given `order` as a
+    /// [`TransactionPage`], see
+    /// [`Client::transactions`](super::Client::transactions)
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn transactions(
+        &self,
+        order: Order,
+        mined: bool,
+        page: Option<&Paginator>,
+    ) -> Result<TransactionPage, Error> {
+        self.call(endpoint::transactions(order, mined, page))
+    }
+
+    /// Gets a specific [`Transaction`] by `id`. Will return None if the
+    /// transaction does not exist
+    ///
+    /// # Errors
+    /// Errors if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub fn get_transaction(&self, id: u32) -> Result<Option<Transaction>, Error> {
+        self.call(endpoint::transaction(id))
+    }
+
+    /// Makes a Kromer [`Transaction`], see
+    /// [`Client::make_transaction`](super::Client::make_transaction)
+    ///
+    /// # Errors
+    /// Errors if both addresses are the same, or the wallet `pk` points to has
+    /// insufficient funds.
+    ///
+    /// See [`Error`] for more info
+    pub fn make_transaction(
+        &self,
+        addr: &Address,
+        amount: Decimal,
+        meta: Option<&str>,
+        pk: &PrivateKey,
+    ) -> Result<Transaction, Error> {
+        let prepared = endpoint::prepare_transaction(self.tag_useragent, addr, amount, meta, pk)?;
+
+        self.submit(&prepared)
+    }
+
+    /// Submits a [`PreparedTransaction`], which may have been prepared on
+    /// another machine
+    ///
+    /// # Errors
+    /// Errors if the sending wallet has insufficient funds or there is a
+    /// network issue.
+    ///
+    /// See [`Error`] for more info
+    pub fn submit(&self, prepared: &PreparedTransaction) -> Result<Transaction, Error> {
+        self.call(endpoint::submit(prepared))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Client;
+    use crate::{
+        Error,
+        http::ClientBuilder,
+        mock::{self, MOTD, MockServer},
+        model::krist::KristError,
+    };
+
+    #[test]
+    fn blocking_requests() {
+        let server = MockServer::respond("200 OK", MOTD);
+
+        let client = Client::new(&server.url).unwrap();
+        assert_eq!(client.get_motd().unwrap().msg, "hi");
+
+        let body =
+            r#"{"ok":false,"error":"address_not_found","message":"Address kaaaaaaaaa not found"}"#;
+        let server = MockServer::respond("404 Not Found", body);
+
+        let client = Client::new(&server.url).unwrap();
+        let addr = "kaaaaaaaaa".parse().unwrap();
+        assert!(matches!(
+            client.get_wallet_addr(&addr),
+            Err(Error::KristResponse {
+                source: KristError::AddrNotFound { .. }
+            })
+        ));
+
+        let client = ClientBuilder::new()
+            .max_response_bytes(16)
+            .build_blocking(&server.url)
+            .unwrap();
+        assert!(matches!(
+            client.get_motd(),
+            Err(Error::ResponseTooLarge { limit: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn transfer_name_target() {
+        let server = MockServer::respond("200 OK", mock::name("example", "kbbbbbbbbb"));
+        let client = Client::new(&server.url).unwrap();
+
+        let name = "example".parse().unwrap();
+        let to = "kbbbbbbbbb".parse().unwrap();
+        let pk = crate::model::PrivateKey::new("key");
+
+        let info = client.transfer_name(&name, &to, &pk).unwrap();
+        assert_eq!(info.owner, to);
+        assert_eq!(server.requests(), ["/api/krist/names/example/transfer"]);
+    }
+}
@@ -0,0 +1,414 @@
+use super::{
+    Basic, Client, DEFAULT_LOOKUP_CHUNK_SIZE, IntoUrl, PKG_NAME, PKG_REPO, PKG_VERSION,
+    circuit::CircuitBreaker,
+};
+use crate::{
+    BadUserAgentSnafu, Error,
+    clock::{Clock, SystemClock},
+    metrics::Metrics,
+};
+use arc_swap::ArcSwapOption;
+use reqwest::header;
+use snafu::ResultExt;
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+use tracing::trace;
+use url::Url;
+
+/// The default limit on the size of a response body, 8 MiB. A page of 1000
+/// transactions, the most any list endpoint returns, is well under a megabyte.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Configures and creates a [`Client`]. Use [`Client::new`] if you are happy
+/// with the defaults.
+///
+/// ```rust
+/// # use kromer_api::{Error, http::ClientBuilder};
+/// # fn run() -> Result<(), Error> {
+/// let client = ClientBuilder::new().build("https://kromer.reconnected.cc")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    accept_invalid_certs: bool,
+    lookup_chunk_size: usize,
+    max_response_bytes: usize,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    tag_useragent: bool,
+    circuit_breaker: Option<(u32, Duration)>,
+    #[cfg(feature = "tokio")]
+    retry: Option<super::RetryPolicy>,
+    decimal_places: Option<u32>,
+    clock: Option<Arc<dyn Clock>>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl ClientBuilder {
+    /// Creates a new [`Self`] using the default settings
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            accept_invalid_certs: false,
+            lookup_chunk_size: DEFAULT_LOOKUP_CHUNK_SIZE,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            pool_max_idle_per_host: usize::MAX,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            tag_useragent: false,
+            circuit_breaker: None,
+            #[cfg(feature = "tokio")]
+            retry: None,
+            decimal_places: None,
+            clock: None,
+            metrics: None,
+        }
+    }
+
+    /// Controls whether the client will accept invalid TLS certificates,
+    /// including self-signed ones. Defaults to `false`. This applies to both
+    /// HTTP requests and any websocket opened from the client.
+    ///
+    /// # Warning
+    /// This disables certificate verification entirely, leaving every request
+    /// open to interception. It exists for testing against a local Kromer2
+    /// instance and should **never** be enabled in production.
+    #[must_use]
+    pub const fn danger_accept_invalid_certs(mut self, v: bool) -> Self {
+        self.accept_invalid_certs = v;
+        self
+    }
+
+    /// Sets the maximum number of addresses sent in a single lookup request,
+    /// see [`Client::lookup_addresses`]. Clamped to at least 1, and defaults to
+    /// [`DEFAULT_LOOKUP_CHUNK_SIZE`]
+    #[must_use]
+    pub fn lookup_chunk_size(mut self, v: usize) -> Self {
+        self.lookup_chunk_size = v.max(1);
+        self
+    }
+
+    /// Sets the largest response body, in bytes, the client will read before
+    /// giving up with [`Error::ResponseTooLarge`]. This protects against
+    /// servers sending bodies large enough to exhaust memory. Defaults to
+    /// [`DEFAULT_MAX_RESPONSE_BYTES`]
+    #[must_use]
+    pub const fn max_response_bytes(mut self, v: usize) -> Self {
+        self.max_response_bytes = v;
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept open to the server.
+    /// Unlimited by default.
+    ///
+    /// Since a client only ever talks to one Kromer2 host, this caps the whole
+    /// pool. A typical bot does fine with the default, but services that make
+    /// bursts of concurrent requests may want to bound it to roughly their
+    /// usual concurrency so bursts don't leave many sockets lingering.
+    #[must_use]
+    pub const fn pool_max_idle_per_host(mut self, v: usize) -> Self {
+        self.pool_max_idle_per_host = v;
+        self
+    }
+
+    /// Sets how long an idle connection is kept open before being closed, or
+    /// `None` to keep them open indefinitely. Defaults to 90 seconds.
+    ///
+    /// Bots that poll the server less often than this will open a new
+    /// connection each time, so raising it can save a TLS handshake per poll.
+    #[must_use]
+    pub const fn pool_idle_timeout(mut self, v: Option<Duration>) -> Self {
+        self.pool_idle_timeout = v;
+        self
+    }
+
+    /// Sets how long a request may take from connecting until the response
+    /// body has been read. Requests that take longer fail with
+    /// [`Error::RequestFailed`]. There is no timeout by default, so a hung
+    /// server can block a request forever.
+    #[must_use]
+    pub const fn timeout(mut self, v: Duration) -> Self {
+        self.timeout = Some(v);
+        self
+    }
+
+    /// Sets how long connecting to the server may take. There is no timeout by
+    /// default.
+    #[must_use]
+    pub const fn connect_timeout(mut self, v: Duration) -> Self {
+        self.connect_timeout = Some(v);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request. Defaults to
+    /// `kromer-api/<version> (<repository>)`
+    #[must_use]
+    pub fn user_agent(mut self, v: &str) -> Self {
+        self.user_agent = Some(v.to_string());
+        self
+    }
+
+    /// Controls whether [`Client::make_transaction`] adds a
+    /// `useragent=kromer-api/<version>` entry to the metadata of every
+    /// transaction, so recipients can see what sent it. Defaults to `false`.
+    ///
+    /// The entry is appended to the end of any metadata you pass, which is
+    /// otherwise sent unchanged, and a `useragent` you set yourself is never
+    /// overwritten.
+    #[must_use]
+    pub const fn tag_useragent(mut self, v: bool) -> Self {
+        self.tag_useragent = v;
+        self
+    }
+
+    /// Enables a circuit breaker, which stops the client from sending requests
+    /// to a server that appears to be down. Disabled by default.
+    ///
+    /// After `threshold` requests in a row fail to reach the server or get a
+    /// 5xx response, every request fails immediately with
+    /// [`Error::CircuitOpen`] for `cooldown`. A single request is then let
+    /// through to test whether the server has recovered, closing the breaker if
+    /// it succeeds or reopening it for another `cooldown` if it fails.
+    #[must_use]
+    pub const fn circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some((threshold, cooldown));
+        self
+    }
+
+    /// Retries idempotent requests that fail to reach the server or get a
+    /// `502`, `503` or `504` response, following `policy`. Disabled by
+    /// default. See [`RetryPolicy`](super::RetryPolicy) for which requests are
+    /// retried.
+    ///
+    /// With a [`circuit_breaker`](Self::circuit_breaker) enabled every retry
+    /// counts towards its threshold, and retrying stops once it opens.
+    #[cfg(feature = "tokio")]
+    #[must_use]
+    pub const fn retry(mut self, policy: super::RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Rounds amounts in responses, such as balances and transaction values,
+    /// to `dp` decimal places, or leaves them as sent if `None`, the default.
+    /// This also covers events and responses on websockets the client opens.
+    ///
+    /// Some servers send amounts as floats with artifacts like
+    /// `1.1000000001`. Kromer tracks amounts to 2 decimal places, so setting
+    /// this to `Some(2)` keeps that noise out of your accounting. Midpoints
+    /// are rounded away from zero.
+    #[must_use]
+    pub const fn round_decimals(mut self, dp: Option<u32>) -> Self {
+        self.decimal_places = dp;
+        self
+    }
+
+    /// Sets the [`Clock`] the client reads the current time from and waits on,
+    /// for retries, polling and the websockets it opens. Defaults to
+    /// [`SystemClock`]
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Installs a [`Metrics`] hook that is notified of every request the
+    /// client, and any websocket it opens, makes
+    #[must_use]
+    pub fn metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Creates a [`Client`] for the server at `url`
+    ///
+    /// # Errors
+    /// Errors if `url` is not a valid [`Url`], or the
+    /// [`user_agent`](Self::user_agent) is not a valid header value
+    ///
+    /// See [`Error`] for more info
+    ///
+    /// # Panics
+    /// Panics if we cannot construct the client for an unknown reason. Chances
+    /// are, if this occurs it is irrecoverable and an issue at the crate level
+    pub fn build(self, url: impl IntoUrl) -> Result<Client<Basic>, Error> {
+        let url = url.into_url()?;
+        let client = self.build_with_headers(url, header::HeaderMap::new())?;
+
+        trace!("Initialized client for {}", client.url);
+
+        Ok(client)
+    }
+
+    /// Creates a [`Client`] that can use Kromer2's internal endpoints
+    ///
+    /// # Errors
+    /// Errors if `url` is not a valid [`Url`], or `key` or the
+    /// [`user_agent`](Self::user_agent) is not a valid header value
+    ///
+    /// See [`Error`] for more info
+    ///
+    /// # Panics
+    /// Panics if we cannot construct the client for an unknown reason. Chances
+    /// are, if this occurs it is irrecoverable and an issue at the crate level
+    #[cfg(feature = "internal")]
+    pub fn build_internal(
+        self,
+        url: impl IntoUrl,
+        key: &str,
+    ) -> Result<Client<super::Priviliged>, Error> {
+        use crate::BadInternalKeySnafu;
+
+        let url = url.into_url()?;
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            "Kromer-Key",
+            header::HeaderValue::from_str(key).context(BadInternalKeySnafu)?,
+        );
+
+        let client = self.build_with_headers(url, headers)?;
+
+        tracing::info!("Initialized client for {}", client.url);
+
+        Ok(client)
+    }
+
+    pub(super) fn build_with_headers<M: super::ClientMarker>(
+        self,
+        url: Url,
+        mut headers: header::HeaderMap,
+    ) -> Result<Client<M>, Error> {
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+
+        let user_agent = self.user_agent_header()?;
+
+        let mut http = reqwest::ClientBuilder::new()
+            .user_agent(user_agent)
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout);
+
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+
+        // Safety:
+        // We can expect here because this should *never* fail unless something is fucked
+        #[allow(clippy::expect_used)]
+        let http = http.build().expect("HTTP is fucked, stop trying");
+
+        Ok(self.with_http(url, http))
+    }
+
+    /// Creates a blocking [`Client`](super::blocking::Client) for the server
+    /// at `url`. The `retry` policy,
+    /// [`circuit_breaker`](Self::circuit_breaker), [`clock`](Self::clock) and
+    /// [`metrics`](Self::metrics) only apply to the async client and are
+    /// ignored.
+    ///
+    /// # Errors
+    /// Errors if `url` is not a valid [`Url`], or the
+    /// [`user_agent`](Self::user_agent) is not a valid header value
+    ///
+    /// See [`Error`] for more info
+    ///
+    /// # Panics
+    /// Panics if we cannot construct the client for an unknown reason, or if
+    /// called from within an async runtime
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self, url: impl IntoUrl) -> Result<super::blocking::Client, Error> {
+        let url = url.into_url()?;
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+
+        // Unlike the async client, the blocking one times out after 30 seconds
+        // unless told otherwise
+        let http = reqwest::blocking::ClientBuilder::new()
+            .user_agent(self.user_agent_header()?)
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout);
+
+        // Safety:
+        // We can expect here because this should *never* fail unless something is fucked
+        #[allow(clippy::expect_used)]
+        let http = http.build().expect("HTTP is fucked, stop trying");
+
+        trace!("Initialized blocking client for {url}");
+
+        Ok(super::blocking::Client::from_parts(
+            url,
+            http,
+            self.max_response_bytes,
+            self.tag_useragent,
+            self.decimal_places,
+        ))
+    }
+
+    /// The `User-Agent` header sent with every request
+    fn user_agent_header(&self) -> Result<header::HeaderValue, Error> {
+        let user_agent = self.user_agent.as_ref().map_or_else(
+            || format!("{PKG_NAME}/{PKG_VERSION} ({PKG_REPO})"),
+            Clone::clone,
+        );
+
+        header::HeaderValue::from_str(&user_agent).context(BadUserAgentSnafu)
+    }
+
+    /// Creates a [`Client`] that sends its requests through `http`, ignoring
+    /// the settings that configure the underlying [`reqwest::Client`]
+    pub(super) fn with_http<M: super::ClientMarker>(
+        self,
+        url: Url,
+        http: reqwest::Client,
+    ) -> Client<M> {
+        Client {
+            url,
+            http,
+
+            lookup_chunk_size: self.lookup_chunk_size,
+            max_response_bytes: self.max_response_bytes,
+            tag_useragent: self.tag_useragent,
+            circuit: self
+                .circuit_breaker
+                .map(|(threshold, cooldown)| CircuitBreaker::new(threshold, cooldown)),
+            #[cfg(feature = "tokio")]
+            retry: self.retry,
+            decimal_places: self.decimal_places,
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            metrics: self.metrics,
+            rate_limit: ArcSwapOption::empty(),
+            #[cfg(feature = "websocket")]
+            ws_tls: super::ws::tls_config(self.accept_invalid_certs),
+
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
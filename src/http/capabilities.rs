@@ -0,0 +1,103 @@
+use super::{Client, ClientMarker};
+use crate::{
+    Error,
+    model::{Address, KromerError, krist::Currency},
+};
+use futures_util::future;
+
+/// What a particular Kromer2 deployment supports, see
+/// [`Client::capabilities`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version of Kromer2 the server is running
+    pub version: String,
+    /// Whether transactions can currently be made
+    pub transactions: bool,
+    /// Whether the lookup API, used by [`Client::lookup_addresses`], is
+    /// available
+    pub lookup: bool,
+    /// The websocket URL advertised by the server
+    pub ws_url: String,
+    /// The currency information advertised by the server, if it sent any
+    pub currency: Option<Currency>,
+    /// Whether the server is running in debug mode
+    pub debug_mode: bool,
+}
+
+impl<M: ClientMarker> Client<M> {
+    /// Works out what this server supports, so one application can work
+    /// against several versions of Kromer2. This fetches the MOTD and probes
+    /// for the lookup API concurrently.
+    ///
+    /// # Errors
+    /// Errors if the MOTD can't be fetched, or if probing the lookup API fails
+    /// for any reason other than the server not having it, such as a network
+    /// issue or a `500`
+    ///
+    /// See [`Error`] for more info
+    pub async fn capabilities(&self) -> Result<Capabilities, Error> {
+        let (motd, lookup) = future::join(
+            self.get_motd(),
+            self.lookup_addresses(&[Address::Serverwelf]),
+        )
+        .await;
+
+        let motd = motd?;
+
+        let lookup = match lookup {
+            Ok(_) => true,
+            Err(Error::KromerResponse {
+                source: KromerError::ResourceNotFoundError,
+                ..
+            }) => false,
+            Err(e) => return Err(e),
+        };
+
+        Ok(Capabilities {
+            version: motd.package.version,
+            transactions: motd.transactions_enabled,
+            lookup,
+            ws_url: motd.public_ws_url,
+            currency: motd.currency,
+            debug_mode: motd.debug_mode,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Error,
+        http::Client,
+        mock::{self, MOTD, MockServer},
+    };
+
+    /// Serves the MOTD, answering lookups with `status` and `body`
+    fn lookup_server(status: &'static str, body: &'static str) -> MockServer {
+        MockServer::new(move |target| {
+            Some(if target.starts_with("/api/lookup/") {
+                mock::json(status, body)
+            } else {
+                mock::json("200 OK", MOTD)
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn lookup_capability() {
+        let found = r#"{"ok":true,"found":0,"notFound":1,"addresses":{"serverwelf":null}}"#;
+        let server = lookup_server("200 OK", found);
+        let caps = Client::new(&server.url).unwrap().capabilities().await;
+        assert!(caps.unwrap().lookup);
+
+        let server = lookup_server("404 Not Found", "");
+        let caps = Client::new(&server.url).unwrap().capabilities().await;
+        assert!(!caps.unwrap().lookup);
+
+        // An outage isn't mistaken for a missing API
+        let body = r#"{"ok":false,"error":"internal_server_error","message":"oops"}"#;
+        let server = lookup_server("500 Internal Server Error", body);
+        let caps = Client::new(&server.url).unwrap().capabilities().await;
+        assert!(matches!(caps, Err(Error::KristResponse { .. })));
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+/// Tracks consecutive failed requests, and stops requests from being sent
+/// while the server appears to be down. See [`ClientBuilder::circuit_breaker`].
+///
+/// The breaker is closed until `threshold` requests in a row fail, at which
+/// point it opens and every request is rejected for `cooldown`. Once the
+/// cooldown has passed a single request is let through to probe the server.
+/// If it succeeds the breaker closes again, otherwise it reopens for another
+/// cooldown.
+///
+/// [`ClientBuilder::circuit_breaker`]: super::ClientBuilder::circuit_breaker
+#[derive(Debug)]
+pub(super) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: TimeDelta,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    failures: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub(super) fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown: TimeDelta::from_std(cooldown).unwrap_or(TimeDelta::MAX),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Returns `true` if a request may be sent at `now`. When the cooldown has
+    /// passed this lets one probe through, and holds the breaker open for
+    /// everyone else until the probe reports back or another cooldown passes.
+    pub(super) fn allow(&self, now: DateTime<Utc>) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match state.open_until {
+            Some(until) if now < until => false,
+            Some(_) => {
+                state.open_until = Some(self.reopen_at(now));
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Records the outcome of a request sent at `now`
+    pub(super) fn record(&self, success: bool, now: DateTime<Utc>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if success {
+            *state = State::default();
+            return;
+        }
+
+        state.failures = state.failures.saturating_add(1);
+
+        if state.failures >= self.threshold {
+            state.open_until = Some(self.reopen_at(now));
+        }
+    }
+
+    fn reopen_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now.checked_add_signed(self.cooldown)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBreaker;
+    use chrono::{DateTime, TimeDelta};
+    use std::time::Duration;
+
+    #[test]
+    fn opens_and_half_opens() {
+        let t0 = DateTime::UNIX_EPOCH;
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+
+        breaker.record(false, t0);
+        assert!(breaker.allow(t0));
+        breaker.record(false, t0);
+        assert!(!breaker.allow(t0 + TimeDelta::seconds(9)));
+
+        // Only one probe is let through after the cooldown
+        let t1 = t0 + TimeDelta::seconds(10);
+        assert!(breaker.allow(t1));
+        assert!(!breaker.allow(t1));
+
+        // A failed probe reopens the breaker
+        breaker.record(false, t1);
+        assert!(!breaker.allow(t1 + TimeDelta::seconds(5)));
+
+        // A successful probe closes it
+        let t2 = t1 + TimeDelta::seconds(10);
+        assert!(breaker.allow(t2));
+        breaker.record(true, t2);
+        assert!(breaker.allow(t2));
+        breaker.record(false, t2);
+        assert!(breaker.allow(t2));
+    }
+}
@@ -0,0 +1,338 @@
+//! The requests sent by both the async [`Client`](super::Client) and the
+//! blocking client. Each is described once here, from its path and body to
+//! how its response becomes the value a client returns, so the two clients
+//! can't drift apart.
+
+use super::{
+    Order, PKG_NAME, PKG_VERSION, Paginator,
+    krist::{
+        AuthRequest, AuthRes, AvailRes, CostRes, GetAddrRes, ListTransactionsQuery, NameRes,
+        RegisterBody, SupplyRes, TransactionRes, TransferBody, UpdateBody,
+    },
+    kromer::KromerResponse,
+};
+use crate::{
+    BadUrlSnafu, Error, MalformedResponseSnafu, ResponseTooLargeSnafu,
+    model::{
+        Address, PrivateKey, Wallet, decimal,
+        krist::{
+            CommonMeta, KristError, Motd, Name, NameInfo, NamePage, PreparedTransaction,
+            RegisterOutcome, Transaction, TransactionPage, WalletPage,
+        },
+    },
+};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, de::IgnoredAny};
+use snafu::{ResultExt, ensure};
+use url::Url;
+use uuid::Uuid;
+
+/// How an endpoint reports errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Api {
+    /// Failures have a non-success status and a Krist error body
+    Krist,
+    /// Every response is wrapped in a [`KromerResponse`], whatever its status
+    Kromer,
+}
+
+/// A request to the Kromer2 API. The response body is read as `R`, which
+/// `finish` turns into the `T` returned to the caller.
+pub struct Endpoint<R, T, Q = (), B = ()> {
+    pub api: Api,
+    pub method: Method,
+    pub path: String,
+    pub query: Option<Q>,
+    pub body: Option<B>,
+    pub finish: fn(Result<R, Error>) -> Result<T, Error>,
+}
+
+impl<Res> Endpoint<Res, Res> {
+    /// A `GET` request to a Krist endpoint
+    pub fn get(path: impl Into<String>) -> Self {
+        Self {
+            api: Api::Krist,
+            method: Method::GET,
+            path: path.into(),
+            query: None,
+            body: None,
+            finish: |res| res,
+        }
+    }
+
+    /// A `POST` request to a Krist endpoint, sending `body` as `JSON`
+    pub fn post<B>(path: impl Into<String>, body: B) -> Endpoint<Res, Res, (), B> {
+        Endpoint {
+            api: Api::Krist,
+            method: Method::POST,
+            path: path.into(),
+            query: None,
+            body: Some(body),
+            finish: |res| res,
+        }
+    }
+}
+
+impl<T> Endpoint<KromerResponse<T>, T> {
+    /// A `GET` request to a Kromer endpoint
+    pub fn kromer(path: impl Into<String>) -> Self {
+        Self {
+            api: Api::Kromer,
+            method: Method::GET,
+            path: path.into(),
+            query: None,
+            body: None,
+            finish: |res| Ok(res?.extract()?),
+        }
+    }
+}
+
+impl<R, T, Q, B> Endpoint<R, T, Q, B> {
+    /// Sends `query` in the query string
+    pub fn query<Q2>(self, query: Option<Q2>) -> Endpoint<R, T, Q2, B> {
+        Endpoint {
+            api: self.api,
+            method: self.method,
+            path: self.path,
+            query,
+            body: self.body,
+            finish: self.finish,
+        }
+    }
+
+    /// Replaces how the response is turned into the returned value
+    pub fn finish<T2>(
+        self,
+        finish: fn(Result<R, Error>) -> Result<T2, Error>,
+    ) -> Endpoint<R, T2, Q, B> {
+        Endpoint {
+            api: self.api,
+            method: self.method,
+            path: self.path,
+            query: self.query,
+            body: self.body,
+            finish,
+        }
+    }
+
+    /// The full URL of this endpoint on the server at `base`
+    pub fn url(&self, base: &Url) -> Result<Url, Error> {
+        base.join(&self.path).context(BadUrlSnafu)
+    }
+}
+
+/// Errors if a response declares a body longer than `limit` bytes
+pub fn check_declared_len(declared: Option<u64>, limit: usize) -> Result<(), Error> {
+    ensure!(
+        usize::try_from(declared.unwrap_or(0)).is_ok_and(|len| len <= limit),
+        ResponseTooLargeSnafu { limit }
+    );
+
+    Ok(())
+}
+
+/// Parses a response `body`, rounding amounts to `decimal_places` if set
+pub fn parse_json<T>(body: &[u8], decimal_places: Option<u32>) -> Result<T, Error>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    decimal::with_precision(decimal_places, || serde_json::from_slice(body))
+        .context(MalformedResponseSnafu)
+}
+
+pub fn motd() -> Endpoint<Motd, Motd> {
+    Endpoint::get("/api/krist/motd")
+}
+
+pub fn wallets_by_uuid(id: &Uuid) -> Endpoint<KromerResponse<Vec<Wallet>>, Vec<Wallet>> {
+    Endpoint::kromer(format!("/api/v1/wallet/by-uuid/{id}"))
+}
+
+pub fn wallets_by_name(name: &str) -> Endpoint<KromerResponse<Vec<Wallet>>, Vec<Wallet>> {
+    Endpoint::kromer(format!("/api/v1/wallet/by-name/{name}"))
+}
+
+pub fn wallet(addr: &Address) -> Endpoint<GetAddrRes, Wallet> {
+    Endpoint::get(format!("/api/krist/addresses/{addr}")).finish(|res| Ok(res?.address.wallet))
+}
+
+/// Like [`wallet`], with a missing address as `None`
+pub fn wallet_opt(addr: &Address) -> Endpoint<GetAddrRes, Option<Wallet>> {
+    wallet(addr).finish(|res| match res {
+        Ok(res) => Ok(Some(res.address.wallet)),
+        Err(Error::KristResponse {
+            source: KristError::AddrNotFound { .. },
+        }) => Ok(None),
+        Err(e) => Err(e),
+    })
+}
+
+pub fn wallet_with_names(addr: &Address) -> Endpoint<GetAddrRes, (Wallet, u32)> {
+    Endpoint::get(format!("/api/krist/addresses/{addr}?fetchNames=true")).finish(|res| {
+        let res = res?.address;
+
+        Ok((res.wallet, res.names))
+    })
+}
+
+pub fn wallets(page: Option<&Paginator>) -> Endpoint<WalletPage, WalletPage, &Paginator> {
+    Endpoint::get("/api/krist/addresses").query(page)
+}
+
+pub fn rich(page: Option<&Paginator>) -> Endpoint<WalletPage, WalletPage, &Paginator> {
+    Endpoint::get("/api/krist/addresses/rich").query(page)
+}
+
+pub fn wallet_transactions<'a>(
+    addr: &Address,
+    mined: bool,
+    page: Option<&'a Paginator>,
+) -> Endpoint<TransactionPage, TransactionPage, &'a Paginator> {
+    Endpoint::get(format!(
+        "/api/krist/addresses/{}/transactions?excludeMined={}",
+        addr, !mined
+    ))
+    .query(page)
+}
+
+pub fn wallet_names<'a>(
+    addr: &Address,
+    page: Option<&'a Paginator>,
+) -> Endpoint<NamePage, NamePage, &'a Paginator> {
+    Endpoint::get(format!("/api/krist/addresses/{addr}/names")).query(page)
+}
+
+pub fn login(pk: &PrivateKey) -> Endpoint<AuthRes, Address, (), AuthRequest<'_>> {
+    Endpoint::post("/api/krist/login", AuthRequest { pk: pk.inner() })
+        .finish(|res| Ok(res?.address))
+}
+
+pub fn supply() -> Endpoint<SupplyRes, Decimal> {
+    Endpoint::get("/api/krist/supply").finish(|res| Ok(res?.money_supply))
+}
+
+pub fn name(name: &Name) -> Endpoint<NameRes, NameInfo> {
+    Endpoint::get(format!("/api/krist/names/{name}")).finish(|res| Ok(res?.name))
+}
+
+pub fn names(page: Option<&Paginator>) -> Endpoint<NamePage, NamePage, &Paginator> {
+    Endpoint::get("/api/krist/names").query(page)
+}
+
+pub fn name_cost() -> Endpoint<CostRes, Decimal> {
+    Endpoint::get("/api/krist/names/cost").finish(|res| Ok(res?.name_cost))
+}
+
+pub fn check_name(name: &Name) -> Endpoint<AvailRes, bool> {
+    Endpoint::get(format!("/api/krist/names/check/{name}")).finish(|res| Ok(res?.available))
+}
+
+/// Registers `name`, with the usual reasons it fails as a [`RegisterOutcome`]
+pub fn register_name<'a>(
+    name: &Name,
+    pk: &'a PrivateKey,
+) -> Endpoint<IgnoredAny, RegisterOutcome, (), RegisterBody<'a>> {
+    Endpoint::post(
+        format!("/api/krist/names/{name}"),
+        RegisterBody { privatekey: pk },
+    )
+    .finish(|res| match res {
+        Ok(_) => Ok(RegisterOutcome::Registered),
+        Err(Error::KristResponse {
+            source: KristError::NameTaken { .. },
+        }) => Ok(RegisterOutcome::NameTaken),
+        Err(Error::KristResponse {
+            source: KristError::InsufficientBalance,
+        }) => Ok(RegisterOutcome::InsufficientFunds),
+        Err(e) => Err(e),
+    })
+}
+
+pub fn transfer_name<'a>(
+    name: &Name,
+    addr: &'a Address,
+    pk: &'a PrivateKey,
+) -> Endpoint<NameRes, NameInfo, (), TransferBody<'a>> {
+    let body = TransferBody {
+        address: addr,
+        privatekey: pk,
+    };
+
+    Endpoint::post(format!("/api/krist/names/{name}/transfer"), body).finish(|res| Ok(res?.name))
+}
+
+pub fn update_name<'a>(
+    name: &Name,
+    meta: Option<&'a str>,
+    pk: &'a PrivateKey,
+) -> Endpoint<NameInfo, NameInfo, (), UpdateBody<'a>> {
+    let body = UpdateBody {
+        privatekey: pk,
+        a: meta,
+    };
+
+    Endpoint::post(format!("/api/krist/names/{name}/update"), body)
+}
+
+pub fn transactions(
+    order: Order,
+    mined: bool,
+    page: Option<&Paginator>,
+) -> Endpoint<TransactionPage, TransactionPage, ListTransactionsQuery<'_>> {
+    let path = match order {
+        Order::Ascending => "/api/krist/transactions",
+        Order::Descending => "/api/krist/transactions/latest",
+    };
+
+    Endpoint::get(path).query(Some(ListTransactionsQuery {
+        exclude_mined: !mined,
+        page,
+    }))
+}
+
+/// Fetches transaction `id`, with a missing transaction as `None`
+pub fn transaction(id: u32) -> Endpoint<TransactionRes, Option<Transaction>> {
+    Endpoint::get(format!("/api/krist/transactions/{id}")).finish(|res| match res {
+        Ok(res) => Ok(Some(res.transaction)),
+        Err(Error::KristResponse {
+            source: KristError::TransactionNotFound,
+        }) => Ok(None),
+        Err(e) => Err(e),
+    })
+}
+
+/// Prepares a transaction, appending a `useragent` entry to `meta` if
+/// `tag_useragent` is set and it doesn't already have one
+pub fn prepare_transaction(
+    tag_useragent: bool,
+    addr: &Address,
+    amount: Decimal,
+    meta: Option<&str>,
+    pk: &PrivateKey,
+) -> Result<PreparedTransaction, Error> {
+    let meta = if tag_useragent {
+        let agent = format!("{PKG_NAME}/{PKG_VERSION}");
+
+        Some(CommonMeta::append_default(
+            meta.unwrap_or_default(),
+            "useragent",
+            &agent,
+        ))
+    } else {
+        meta.map(ToString::to_string)
+    };
+
+    Ok(PreparedTransaction::new(
+        addr,
+        amount,
+        meta.as_deref(),
+        pk.clone(),
+    )?)
+}
+
+pub fn submit(
+    prepared: &PreparedTransaction,
+) -> Endpoint<TransactionRes, Transaction, (), &PreparedTransaction> {
+    Endpoint::post("/api/krist/transactions", prepared).finish(|res| Ok(res?.transaction))
+}
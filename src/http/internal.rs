@@ -1,19 +1,44 @@
 use super::ClientMarkerSealed;
 use crate::{
-    BadInternalKeySnafu, BadRequestSnafu, BadUrlSnafu, Error, MalformedResponseSnafu,
-    http::{Client, kromer::KromerResponse},
+    BadRequestSnafu, BadUrlSnafu, Error,
+    http::{Client, ClientBuilder, IntoUrl, kromer::KromerResponse},
     model::{Address, PrivateKey, Wallet},
 };
-use reqwest::{Request, header};
+use futures_util::{StreamExt, stream};
+use reqwest::Request;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use snafu::ResultExt;
-use std::marker::PhantomData;
-use tracing::info;
-use url::Url;
 use uuid::Uuid;
 
 /// A marker type denoting a [`Client`](super::Client) that can use internal endpoints
+///
+/// The only ways to get one are [`Client::new_internal`] and
+/// [`ClientBuilder::build_internal`], which attach the `Kromer-Key` header
+/// these endpoints need.
+///
+/// ```no_run
+/// # use kromer_api::{Error, http::Client, model::Address};
+/// # use rust_decimal::Decimal;
+/// # async fn run() -> Result<(), Error> {
+/// let client = Client::new_internal("https://kromer.reconnected.cc", "key")?;
+/// client.give_money(&Address::Serverwelf, Decimal::ONE).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Clients made any other way can't call internal methods at all:
+///
+/// ```compile_fail
+/// # use kromer_api::{Error, http::Client, model::Address};
+/// # use rust_decimal::Decimal;
+/// # async fn run() -> Result<(), Error> {
+/// let client = Client::new("https://kromer.reconnected.cc")?;
+/// client.give_money(&Address::Serverwelf, Decimal::ONE).await?;
+/// # Ok(())
+/// # }
+/// ```
 pub struct Priviliged;
 
 impl ClientMarkerSealed for Priviliged {}
@@ -22,59 +47,37 @@ impl Client<Priviliged> {
     /// Create a new client for the Kromer2 API. This will reuse connections.
     ///
     /// # Errors
-    /// Errors if `url` is not a valid [`Url`]
+    /// Errors if `url` is not a valid [`Url`](url::Url)
     ///
     /// See [`Error`] for more info
     ///
     /// # Panics
     /// Panics if we cannot construct the client for an unknown reason. Chances are, if this occurs
     /// it is irrecoverable and an issue at the crate level
-    pub fn new_internal(url: &str, key: &str) -> Result<Self, Error> {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
-        );
-        headers.insert(
-            "Kromer-Key",
-            header::HeaderValue::from_str(key).context(BadInternalKeySnafu)?,
-        );
-
-        let client = Self {
-            url: Url::parse(url).context(BadUrlSnafu)?,
-
-            // Safety:
-            // We can expect here because this should *never* fail uness something is fucked
-            #[allow(clippy::expect_used)]
-            http: reqwest::ClientBuilder::new()
-                .default_headers(headers)
-                .build()
-                .expect("HTTP is fucked, stop trying"),
-
-            _marker: PhantomData,
-        };
-
-        info!("Initialized client for {}", client.url);
-
-        Ok(client)
+    pub fn new_internal(url: impl IntoUrl, key: &str) -> Result<Self, Error> {
+        ClientBuilder::new().build_internal(url, key)
     }
 
     async fn internal_query<T>(&self, req: Request) -> Result<T, Error>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let resp = self.query(req).await?;
+        let res = async {
+            let resp = self.query(req).await?;
 
-        if !resp.status().is_success() {
-            resp.json::<KromerResponse<i32>>()
-                .await
-                .context(MalformedResponseSnafu)?
-                .extract()?;
+            if !resp.status().is_success() {
+                self.read_json::<KromerResponse<i32>>(resp)
+                    .await?
+                    .extract()?;
 
-            unreachable!()
+                unreachable!()
+            }
+
+            self.read_json(resp).await
         }
+        .await;
 
-        resp.json::<T>().await.context(MalformedResponseSnafu)
+        self.observe(res)
     }
 
     async fn internal_get<T>(&self, endpoint: &str) -> Result<T, Error>
@@ -108,7 +111,12 @@ impl Client<Priviliged> {
     }
 
     /// Gets all [`Wallets`](Wallet) owned by `id`, along with the 32 byte hash of
-    /// {address}{private key} for each wallet.
+    /// {address}{private key} for each wallet. Use [`internal_key_hash`] to
+    /// check a [`PrivateKey`] you hold against it.
+    ///
+    /// This is the only implementation of Kromer2's `by-player` endpoint in
+    /// the crate, and reads the server's `private_key` field as an array of
+    /// 32 bytes.
     ///
     /// # Errors
     /// Errors if there is a network error or you are unauthorized
@@ -150,6 +158,43 @@ impl Client<Priviliged> {
             .await?
             .wallet)
     }
+
+    /// Credits many wallets at once, such as for an airdrop, with at most
+    /// `concurrency` requests in flight at a time. A failure to credit one
+    /// wallet doesn't stop the others.
+    ///
+    /// Results are returned in the same order as `credits`, paired with the
+    /// [`Address`] they belong to.
+    pub async fn give_money_batch(
+        &self,
+        credits: &[(Address, Decimal)],
+        concurrency: usize,
+    ) -> Vec<(Address, Result<Wallet, Error>)> {
+        stream::iter(credits)
+            .map(|(addr, amount)| async move { (*addr, self.give_money(addr, *amount).await) })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// Rebuilds the hash [`Client::get_wallet_internal`] returns for a wallet,
+/// the SHA-256 of `addr` followed by `pk`
+///
+/// The derivation comes from Kromer2's API docs and hasn't been confirmed
+/// against the server source, so a mismatch doesn't prove the key is wrong.
+///
+/// ```
+/// # use kromer_api::{http::internal_key_hash, model::{Address, PrivateKey}};
+/// let pk = PrivateKey::from("key");
+/// let addr = Address::try_from("kaaaaaaaaa").unwrap();
+/// let hash = internal_key_hash(&addr, &pk);
+///
+/// assert_eq!(hash[..4], [0x80, 0xe3, 0xa2, 0x6b]);
+/// ```
+#[must_use]
+pub fn internal_key_hash(addr: &Address, pk: &PrivateKey) -> [u8; 32] {
+    Sha256::digest(format!("{addr}{}", pk.inner())).into()
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -182,3 +227,23 @@ struct GiveMoneyBody<'a> {
     addr: &'a Address,
     amount: Decimal,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_hash_matches_vector() {
+        let addr = Address::try_from("kaaaaaaaaa").unwrap();
+        let pk = PrivateKey::from("key");
+
+        // sha256("kaaaaaaaaakey")
+        let expected = [
+            0x80, 0xe3, 0xa2, 0x6b, 0x1d, 0x77, 0xef, 0xbb, 0xc5, 0xc4, 0x06, 0xd3, 0xfe, 0xd1,
+            0x4b, 0xd7, 0x38, 0x2f, 0xd8, 0xb5, 0x28, 0xf1, 0xef, 0xa5, 0x40, 0xa0, 0xb7, 0xbf,
+            0xfe, 0x24, 0x07, 0xc0,
+        ];
+
+        assert_eq!(internal_key_hash(&addr, &pk), expected);
+    }
+}
@@ -1,13 +1,15 @@
 use crate::{
+    Error,
     http::Paginator,
     model::{
-        Address, PrivateKey, Wallet,
+        Address, KromerError, PrivateKey, Wallet,
         krist::{KristError, NameInfo, Transaction, UnexpectedResponseSnafu},
     },
 };
+use reqwest::StatusCode;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use snafu::{OptionExt, ensure};
+use snafu::OptionExt;
 
 #[derive(Debug, Deserialize)]
 pub struct RawKristError {
@@ -16,13 +18,16 @@ pub struct RawKristError {
 }
 
 impl RawKristError {
-    pub fn parse(self) -> Result<(), KristError> {
+    /// Maps the error to a [`KristError`]. `status` is the HTTP status code of
+    /// the response the error came from, if any.
+    pub fn parse(self, status: Option<u16>) -> Result<(), KristError> {
         let find_between = |first: &str, last: &str| -> Result<&str, KristError> {
             let word_start =
                 first.len() + self.message.find(first).context(UnexpectedResponseSnafu)?;
-            let word_end = self.message.find(last).context(UnexpectedResponseSnafu)?;
+            let rest = &self.message[word_start..];
+            let word_end = rest.find(last).context(UnexpectedResponseSnafu)?;
 
-            Ok(&self.message[word_start..word_end])
+            Ok(&rest[..word_end])
         };
 
         Err(match self.error.as_str() {
@@ -43,32 +48,51 @@ impl RawKristError {
                 KristError::NameTaken { name }
             }
             "not_name_owner" => {
-                ensure!(self.message.len() > 30, UnexpectedResponseSnafu);
-
-                let name = self.message[31..].to_string();
+                let name = self
+                    .message
+                    .get(31..)
+                    .context(UnexpectedResponseSnafu)?
+                    .to_string();
 
                 KristError::NotNameOwner { name }
             }
-            "insufficient_balance" | "insufficient_funds        ..user1" => {
-                KristError::InsufficientBalance
-            }
+            "insufficient_balance" | "insufficient_funds" => KristError::InsufficientBalance,
             "transaction_not_found" => KristError::TransactionNotFound,
             "transactions_disabled" => KristError::TransactionsDisabled,
             "same_wallet_transfer" => KristError::SameWalletTransfer,
             "transaction_conflict" => {
-                ensure!(self.message.len() > 35, UnexpectedResponseSnafu);
-
-                let param = self.message[36..].to_string();
+                let param = self
+                    .message
+                    .get(36..)
+                    .context(UnexpectedResponseSnafu)?
+                    .to_string();
 
                 KristError::TransactionConflict { param }
             }
             _ => KristError::InternalServerError {
                 message: self.message,
+                status,
             },
         })
     }
 }
 
+/// The error for a response with a non-success `status`, given the result of
+/// reading its body as a [`RawKristError`]
+pub fn response_error(raw: Result<RawKristError, Error>, status: StatusCode) -> Error {
+    match raw {
+        // Routes the server doesn't implement 404 without a Krist error body
+        Err(Error::MalformedResponse { .. }) if status == StatusCode::NOT_FOUND => {
+            KromerError::ResourceNotFoundError.into()
+        }
+        Err(e) => e,
+        Ok(raw) => match raw.parse(Some(status.as_u16())) {
+            Err(e) => e.into(),
+            Ok(()) => KristError::UnexpectedResponse.into(),
+        },
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetAddrRes {
     pub address: GetAddrInner,
@@ -95,6 +119,7 @@ pub struct AuthRes {
 
 #[derive(Debug, Deserialize)]
 pub struct SupplyRes {
+    #[serde(deserialize_with = "crate::model::decimal::string_or_number")]
     pub money_supply: Decimal,
 }
 
@@ -105,6 +130,7 @@ pub struct NameRes {
 
 #[derive(Debug, Deserialize)]
 pub struct CostRes {
+    #[serde(deserialize_with = "crate::model::decimal::string_or_number")]
     pub name_cost: Decimal,
 }
 
@@ -143,10 +169,59 @@ pub struct TransactionRes {
     pub transaction: Transaction,
 }
 
-#[derive(Debug, Serialize, Clone, Copy)]
-pub struct MakeTransactionBody<'a> {
-    pub privatekey: &'a PrivateKey,
-    pub to: &'a Address,
-    pub metadata: Option<&'a str>,
-    pub amount: Decimal,
+#[cfg(test)]
+mod tests {
+    use super::RawKristError;
+    use crate::model::krist::KristError;
+
+    fn parse(error: &str, message: &str) -> KristError {
+        RawKristError {
+            error: error.to_string(),
+            message: message.to_string(),
+        }
+        .parse(None)
+        .unwrap_err()
+    }
+
+    #[test]
+    fn malformed_messages_dont_panic() {
+        // The closing phrase appearing before the opening one used to slice
+        // out of order
+        assert_eq!(
+            parse("name_not_found", " not found Name "),
+            KristError::UnexpectedResponse
+        );
+
+        // Multi-byte characters straddling the fixed offsets used to panic
+        assert_eq!(
+            parse("not_name_owner", &"é".repeat(16)),
+            KristError::UnexpectedResponse
+        );
+        assert_eq!(
+            parse("transaction_conflict", "short"),
+            KristError::UnexpectedResponse
+        );
+
+        assert_eq!(
+            parse("name_not_found", "Name example not found"),
+            KristError::NameNotFound {
+                name: "example".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        let json = r#"{"ok":false,"error":"insufficient_funds","message":"Insufficient funds"}"#;
+        let raw: RawKristError = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            raw.parse(Some(403)).unwrap_err(),
+            KristError::InsufficientBalance
+        );
+        assert_eq!(
+            parse("insufficient_balance", "Insufficient balance"),
+            KristError::InsufficientBalance
+        );
+    }
 }
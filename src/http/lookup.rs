@@ -0,0 +1,199 @@
+use super::{Client, ClientMarker, Paginator, TransactionKind};
+use crate::{
+    Error,
+    model::{
+        Address, KromerError, Wallet,
+        krist::{KristError, TransactionPage},
+    },
+};
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write};
+
+/// The number of addresses Kromer2's lookup endpoint accepts in a single
+/// request
+pub const DEFAULT_LOOKUP_CHUNK_SIZE: usize = 128;
+
+impl<M: ClientMarker> Client<M> {
+    /// Fetches many [`Wallets`](Wallet) at once using the lookup API. Addresses
+    /// that could not be found are omitted from the result.
+    ///
+    /// The lookup endpoint caps how many addresses can be requested at once, so
+    /// `addrs` is split into chunks of at most
+    /// [`lookup_chunk_size`](super::ClientBuilder::lookup_chunk_size)
+    /// addresses which are fetched concurrently.
+    ///
+    /// # Errors
+    /// Errors with [`KromerError::ResourceNotFoundError`] if the server doesn't
+    /// support the lookup API, or if any of the requests fail
+    ///
+    /// See [`Error`] for more info
+    pub async fn lookup_addresses(
+        &self,
+        addrs: &[Address],
+    ) -> Result<HashMap<Address, Wallet>, Error> {
+        let chunks = addrs
+            .chunks(self.lookup_chunk_size)
+            .map(|chunk| self.lookup_chunk(chunk));
+
+        let mut res = HashMap::with_capacity(addrs.len());
+
+        for chunk in try_join_all(chunks).await? {
+            res.extend(
+                chunk
+                    .addresses
+                    .into_iter()
+                    .filter_map(|(addr, wallet)| Some((addr, wallet?))),
+            );
+        }
+
+        Ok(res)
+    }
+
+    async fn lookup_chunk(&self, addrs: &[Address]) -> Result<LookupAddrsRes, Error> {
+        let mut url = String::from("/api/lookup/addresses/");
+
+        for (i, addr) in addrs.iter().enumerate() {
+            if i != 0 {
+                url.push(',');
+            }
+            let _ = write!(url, "{addr}");
+        }
+
+        // Missing addresses are reported in the body, so a 404 means the
+        // endpoint itself doesn't exist
+        endpoint_missing(self.krist_get(&url, None::<()>).await)
+    }
+
+    /// Lists the transactions involving `addr` as a [`TransactionPage`] using
+    /// the lookup API, newest first. `kind` picks whether to list the
+    /// transactions it sent, received, or both.
+    ///
+    /// The direction is sent as a `kind` query parameter, left out for
+    /// [`TransactionKind::All`]. A filtered page can hold fewer transactions
+    /// than its `total` suggests, including none at all.
+    ///
+    /// # Errors
+    /// Errors with [`KromerError::ResourceNotFoundError`] if the server doesn't
+    /// support the lookup API, or if there is a network issue
+    ///
+    /// See [`Error`] for more info
+    pub async fn lookup_address_transactions(
+        &self,
+        addr: &Address,
+        kind: TransactionKind,
+        page: Option<&Paginator>,
+    ) -> Result<TransactionPage, Error> {
+        let url = format!("/api/lookup/transactions/{addr}");
+
+        let query = LookupTransactionsQuery {
+            kind: (kind != TransactionKind::All).then_some(kind),
+            page,
+        };
+
+        endpoint_missing(self.krist_get(&url, Some(query)).await)
+    }
+}
+
+/// Reports a lookup endpoint that responded with a 404 as
+/// [`KromerError::ResourceNotFoundError`]
+fn endpoint_missing<T>(res: Result<T, Error>) -> Result<T, Error> {
+    match res {
+        Err(Error::KristResponse {
+            source:
+                KristError::InternalServerError {
+                    status: Some(404), ..
+                },
+        }) => Err(KromerError::ResourceNotFoundError.into()),
+        res => res,
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LookupTransactionsQuery<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<TransactionKind>,
+    #[serde(flatten)]
+    page: Option<&'a Paginator>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupAddrsRes {
+    addresses: HashMap<Address, Option<Wallet>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Error,
+        http::{Client, Paginator, TransactionKind},
+        mock::{self, MockServer},
+        model::KromerError,
+    };
+
+    #[tokio::test]
+    async fn lookup_address_transactions() {
+        let addr = "kaaaaaaaaa".parse().unwrap();
+        let page = Paginator::new(0, 10);
+
+        let cases = [
+            (
+                TransactionKind::Sent,
+                "/api/lookup/transactions/kaaaaaaaaa?kind=sent&limit=10&offset=0",
+            ),
+            (
+                TransactionKind::Received,
+                "/api/lookup/transactions/kaaaaaaaaa?kind=received&limit=10&offset=0",
+            ),
+            (
+                TransactionKind::All,
+                "/api/lookup/transactions/kaaaaaaaaa?limit=10&offset=0",
+            ),
+        ];
+
+        for (kind, path) in cases {
+            // Filtered pages may come back empty despite a nonzero total
+            let server = MockServer::respond("200 OK", mock::transaction_page(&[], 5));
+            let client = Client::new(&server.url).unwrap();
+
+            let res = client
+                .lookup_address_transactions(&addr, kind, Some(&page))
+                .await
+                .unwrap();
+            assert!(res.items.is_empty());
+            assert_eq!(res.total, 5);
+
+            assert_eq!(server.requests(), [path]);
+        }
+    }
+
+    #[tokio::test]
+    async fn lookup_addresses() {
+        let body = r#"{"ok":true,"found":2,"notFound":1,"addresses":{"kaaaaaaaaa":{"address":"kaaaaaaaaa","balance":10,"totalin":10,"totalout":0,"firstseen":"2025-01-01T00:00:00Z"},"kbbbbbbbbb":{"address":"kbbbbbbbbb","balance":5.5,"totalin":6,"totalout":0.5,"firstseen":"2025-01-01T00:00:00Z"},"kccccccccc":null}}"#;
+        let server = MockServer::respond("200 OK", body);
+
+        let addrs = ["kaaaaaaaaa", "kbbbbbbbbb", "kccccccccc"].map(|a| a.parse().unwrap());
+        let client = Client::new(&server.url).unwrap();
+
+        let wallets = client.lookup_addresses(&addrs).await.unwrap();
+        assert_eq!(wallets.len(), 2);
+        assert_eq!(wallets[&addrs[1]].address, addrs[1]);
+        assert!(!wallets.contains_key(&addrs[2]));
+
+        assert_eq!(
+            server.requests(),
+            ["/api/lookup/addresses/kaaaaaaaaa,kbbbbbbbbb,kccccccccc"]
+        );
+
+        // Servers without the lookup API
+        let server = MockServer::respond("404 Not Found", "");
+        let client = Client::new(&server.url).unwrap();
+
+        assert!(matches!(
+            client.lookup_addresses(&addrs).await,
+            Err(Error::KromerResponse {
+                source: KromerError::ResourceNotFoundError
+            })
+        ));
+    }
+}
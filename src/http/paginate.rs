@@ -0,0 +1,83 @@
+use super::Paginator;
+use crate::{Error, model::krist::Page};
+use futures_util::{Stream, stream};
+use std::collections::VecDeque;
+
+/// The page size used when streaming through a paginated endpoint. This is the
+/// largest limit the Krist API accepts.
+pub const STREAM_PAGE_LIMIT: usize = 1000;
+
+/// Implemented by responses from paginated endpoints so that they can be walked
+/// by [`paginate`]
+pub trait Paged {
+    type Item;
+
+    /// Splits the page into its items and the total number of items that can
+    /// be fetched from the endpoint
+    fn into_parts(self) -> (Vec<Self::Item>, usize);
+}
+
+impl<T> Paged for Page<T> {
+    type Item = T;
+
+    fn into_parts(self) -> (Vec<Self::Item>, usize) {
+        (self.items, self.total)
+    }
+}
+
+struct State<F, T> {
+    fetch: F,
+    page: Paginator,
+    buf: VecDeque<T>,
+    fetched: usize,
+    done: bool,
+}
+
+/// Walks a paginated endpoint one page at a time, yielding each item
+/// individually. Stops once `total` items have been fetched, an empty page is
+/// returned, or an error is encountered. Errors are yielded before the stream
+/// ends.
+pub fn paginate<'a, P, F, Fut>(fetch: F) -> impl Stream<Item = Result<P::Item, Error>> + 'a
+where
+    P: Paged + 'a,
+    P::Item: 'a,
+    F: FnMut(Paginator) -> Fut + 'a,
+    Fut: Future<Output = Result<P, Error>> + 'a,
+{
+    let state = State {
+        fetch,
+        page: Paginator::new(0, STREAM_PAGE_LIMIT),
+        buf: VecDeque::new(),
+        fetched: 0,
+        done: false,
+    };
+
+    stream::unfold(state, |mut st| async move {
+        loop {
+            if let Some(item) = st.buf.pop_front() {
+                return Some((Ok(item), st));
+            }
+
+            if st.done {
+                return None;
+            }
+
+            match (st.fetch)(st.page).await {
+                Ok(page) => {
+                    let (items, total) = page.into_parts();
+
+                    // Servers may return fewer items than the limit, so the
+                    // next page starts after what was actually received
+                    st.fetched += items.len();
+                    st.page.set_offset(st.fetched);
+                    st.done = items.is_empty() || st.fetched >= total;
+                    st.buf.extend(items);
+                }
+                Err(e) => {
+                    st.done = true;
+                    return Some((Err(e), st));
+                }
+            }
+        }
+    })
+}
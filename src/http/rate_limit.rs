@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// Rate limit information reported by the server through the
+/// `X-RateLimit-*` headers of a response. See [`Client::last_rate_limit`].
+///
+/// Every field is optional, as servers differ in which headers they send.
+///
+/// [`Client::last_rate_limit`]: super::Client::last_rate_limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// The number of requests allowed in the current window
+    pub limit: Option<u64>,
+    /// The number of requests left in the current window
+    pub remaining: Option<u64>,
+    /// How long until the current window resets
+    pub reset: Option<Duration>,
+    /// When the response carrying this information was received, according to
+    /// the client's [`Clock`](crate::clock::Clock)
+    pub received_at: DateTime<Utc>,
+}
+
+impl RateLimitInfo {
+    /// Reads rate limit headers from `headers`, returning `None` if there are
+    /// none
+    pub(super) fn from_headers(headers: &HeaderMap, received_at: DateTime<Utc>) -> Option<Self> {
+        let get = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let limit = get("x-ratelimit-limit");
+        let remaining = get("x-ratelimit-remaining");
+        let reset = get("x-ratelimit-reset").map(Duration::from_secs);
+
+        (limit.is_some() || remaining.is_some() || reset.is_some()).then_some(Self {
+            limit,
+            remaining,
+            reset,
+            received_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitInfo;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::time::Duration;
+
+    #[test]
+    fn parse_headers() {
+        let now = chrono::DateTime::UNIX_EPOCH;
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(RateLimitInfo::from_headers(&headers, now), None);
+
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("41"));
+        headers.insert("X-RateLimit-Reset", HeaderValue::from_static("30"));
+        headers.insert("X-RateLimit-Limit", HeaderValue::from_static("lots"));
+
+        assert_eq!(
+            RateLimitInfo::from_headers(&headers, now),
+            Some(RateLimitInfo {
+                limit: None,
+                remaining: Some(41),
+                reset: Some(Duration::from_secs(30)),
+                received_at: now,
+            })
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::Error;
+use reqwest::{Method, Response, StatusCode};
+use std::{
+    hash::{BuildHasher, RandomState},
+    time::Duration,
+};
+
+/// Controls how a [`Client`](super::Client) retries requests that failed for
+/// a reason that is likely to go away on its own. See
+/// [`ClientBuilder::retry`](super::ClientBuilder::retry).
+///
+/// Only idempotent requests, such as looking up a wallet, are retried, and
+/// only if they could not reach the server or got a `502`, `503` or `504`
+/// response. Requests that change state on the server, like
+/// [`Client::make_transaction`](super::Client::make_transaction), are never
+/// retried, as a request that timed out may still have gone through.
+///
+/// Before retry `n` the client waits `base_delay * 2^(n - 1)`. With jitter
+/// enabled, a random amount of up to half that delay is taken off, so clients
+/// that failed together don't all retry at the same moment.
+///
+/// ```rust
+/// # use kromer_api::http::RetryPolicy;
+/// # use std::time::Duration;
+/// let policy = RetryPolicy::new()
+///     .base_delay(Duration::from_millis(100))
+///     .jitter(false);
+///
+/// assert_eq!(policy.delay(1), Duration::from_millis(100));
+/// assert_eq!(policy.delay(3), Duration::from_millis(400));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Creates a policy that makes up to 3 attempts, starting at a 250
+    /// millisecond delay, with jitter
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            jitter: true,
+        }
+    }
+
+    /// Sets how many times a request is sent before giving up, including the
+    /// first attempt. Clamped to at least 1, which disables retries.
+    #[must_use]
+    pub const fn max_attempts(mut self, v: u32) -> Self {
+        self.max_attempts = if v == 0 { 1 } else { v };
+        self
+    }
+
+    /// Sets the delay before the first retry, which doubles after each one
+    #[must_use]
+    pub const fn base_delay(mut self, v: Duration) -> Self {
+        self.base_delay = v;
+        self
+    }
+
+    /// Controls whether delays are randomly shortened by up to half
+    #[must_use]
+    pub const fn jitter(mut self, v: bool) -> Self {
+        self.jitter = v;
+        self
+    }
+
+    /// The delay before retry number `retry`, counting from 1
+    #[must_use]
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+
+        if !self.jitter {
+            return delay;
+        }
+
+        // `RandomState` is randomly seeded, which is plenty for spreading
+        // retries out without pulling in an RNG
+        let half = delay / 2;
+        let nanos = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX);
+        let offset = RandomState::new().hash_one(retry) % nanos.saturating_add(1);
+
+        delay.saturating_sub(Duration::from_nanos(offset))
+    }
+
+    /// Whether a request that was attempt number `attempt` may be retried
+    pub(super) const fn allows_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether sending a request with `method` twice has the same effect as
+/// sending it once
+pub(super) const fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Whether `res` failed in a way that retrying might fix
+pub(super) fn is_transient(res: &Result<Response, Error>) -> bool {
+    match res {
+        Ok(response) => matches!(
+            response.status(),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ),
+        Err(e) => e.is_transient(),
+    }
+}
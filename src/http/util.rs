@@ -1,12 +1,16 @@
+use crate::{BadUrlSnafu, Error};
 use serde::Serialize;
+use snafu::ResultExt;
+use url::Url;
 
-/// Implemented by valid [`Client`](super::Client) markers.
+/// Implemented by valid [`Client`](super::Client) markers. All markers are
+/// `Send + Sync` so that they never affect whether a client can be shared.
 #[allow(private_bounds)]
 pub trait ClientMarker: ClientMarkerSealed {}
 
 impl<T: ClientMarkerSealed> ClientMarker for T {}
 
-pub(super) trait ClientMarkerSealed: Send + Sync {}
+pub(super) trait ClientMarkerSealed: Send + Sync + 'static {}
 
 /// A marker type denoting a [`Client`](super::Client) that cannot use internal
 /// endpoints
@@ -14,11 +18,87 @@ pub struct Basic;
 
 impl ClientMarkerSealed for Basic {}
 
+/// Types that can be turned into the [`Url`] a [`Client`](super::Client)
+/// connects to. Implemented for [`Url`] itself, which skips parsing, and for
+/// strings.
+#[allow(private_bounds)]
+pub trait IntoUrl: IntoUrlSealed {}
+
+impl<T: IntoUrlSealed> IntoUrl for T {}
+
+pub(super) trait IntoUrlSealed {
+    fn into_url(self) -> Result<Url, Error>;
+}
+
+impl IntoUrlSealed for Url {
+    fn into_url(self) -> Result<Url, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoUrlSealed for &Url {
+    fn into_url(self) -> Result<Url, Error> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoUrlSealed for &str {
+    fn into_url(self) -> Result<Url, Error> {
+        Url::parse(self).context(BadUrlSnafu)
+    }
+}
+
+impl IntoUrlSealed for String {
+    fn into_url(self) -> Result<Url, Error> {
+        self.as_str().into_url()
+    }
+}
+
+impl IntoUrlSealed for &String {
+    fn into_url(self) -> Result<Url, Error> {
+        self.as_str().into_url()
+    }
+}
+
+/// The order transactions are listed in. See [`Client::transactions`] and
+/// [`Client::wallet_transactions`].
+///
+/// The offset of a [`Paginator`] always counts from the start of the chosen
+/// order, so offset 0 is the oldest transaction when ascending and the newest
+/// when descending.
+///
+/// [`Client::transactions`]: super::Client::transactions
+/// [`Client::wallet_transactions`]: super::Client::wallet_transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Oldest first
+    Ascending,
+    /// Newest first
+    #[default]
+    Descending,
+}
+
+/// Which of an address' transactions to list. See
+/// [`Client::lookup_address_transactions`].
+///
+/// [`Client::lookup_address_transactions`]: super::Client::lookup_address_transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    /// Transactions sent from the address
+    Sent,
+    /// Transactions sent to the address
+    Received,
+    /// Every transaction involving the address
+    #[default]
+    All,
+}
+
 /// Used for paginating various Krist endpoints
 #[derive(Debug, Serialize, Clone, Copy, Eq, PartialEq)]
 pub struct Paginator {
-    limit: usize,
-    offset: usize,
+    pub(super) limit: usize,
+    pub(super) offset: usize,
 }
 
 impl Paginator {
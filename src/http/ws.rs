@@ -1,49 +1,53 @@
 use crate::{
-    Error,
-    model::{PrivateKey, ws::WebSocketEvent},
-    ws::{Guest, WebSocketError, WsClient, WsConfig, WsState},
+    BadUrlSnafu, Error, RequestFailedSnafu, clock,
+    model::{
+        Address, PrivateKey,
+        krist::Transaction,
+        ws::{SubscriptionType, WebSocketEvent},
+    },
+    ws::{Guest, Reconnector, WebSocketError, WsClient, WsConfig, WsNetSnafu, WsState},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, future};
+use reqwest::Response;
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
 };
-use rustls::{ClientConfig, RootCertStore};
 use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::{pin::pin, sync::Arc, time::Duration};
 use tokio::sync::mpsc::Receiver;
 use tokio_tungstenite::{Connector, connect_async_tls_with_config};
 use tracing::instrument;
 use url::Url;
 
-use super::{Client, ClientMarker};
+use super::{Client, ClientMarker, endpoint::Endpoint};
 
 impl<M: ClientMarker> Client<M> {
     /// Start websocket session, creating a [`WsClient`]. By default, this will be subscribed to
-    /// nothing. Consider using the [`Self::connnect_ws_config`] method instead if you know what
-    /// events you'd like to be subscribed to.
+    /// nothing, which takes a round trip to unsubscribe from each of the
+    /// [`DEFAULT_SUBSCRIPTIONS`](crate::ws::DEFAULT_SUBSCRIPTIONS). Consider using the
+    /// [`Self::connnect_ws_config`] method instead if you know what events you'd like to be
+    /// subscribed to, or don't mind receiving the defaults.
     ///
     /// # Errors
     /// Will error if the client cannot be created
     #[instrument(skip_all)]
     pub async fn connect_ws(&self) -> Result<(WsClient<Guest>, Receiver<WebSocketEvent>), Error> {
-        let url = self
-            .krist_post::<WsConnRes>("/api/krist/ws/start", ())
-            .await?
-            .url;
-
-        let root_store = RootCertStore {
-            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
-        };
+        let url = self.ws_url(None).await?;
 
-        let connector = Connector::Rustls(
-            ClientConfig::builder()
-                .with_root_certificates(root_store)
-                .with_no_client_auth()
-                .into(),
-        );
-
-        let (stream, _) = connect_async_tls_with_config(url.as_str(), None, false, Some(connector))
-            .await
-            .map_err(|err| WebSocketError::WsNetError {
-                source: Box::from(err),
-            })?;
+        let stream = connect(&url, self.ws_tls.clone()).await?;
 
-        Ok(WsClient::new(stream).await)
+        Ok(WsClient::new(
+            stream,
+            self.metrics.clone(),
+            Arc::clone(&self.clock),
+            self.decimal_places,
+        )
+        .await)
     }
 
     /// Start a websocket session, constructing it using [`WsConfig`].
@@ -54,29 +58,217 @@ impl<M: ClientMarker> Client<M> {
         &self,
         cfg: WsConfig<S>,
     ) -> Result<(WsClient<S>, Receiver<WebSocketEvent>), Error> {
-        let url = self
-            .krist_post::<WsConnRes>("/api/krist/ws/start", WsConnBody { privatekey: cfg.pk })
-            .await?
-            .url;
+        let url = self.ws_url(cfg.pk.clone()).await?;
 
-        let root_store = RootCertStore {
-            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
-        };
+        let stream = connect(&url, self.ws_tls.clone()).await?;
+
+        let connector = cfg
+            .reconnect
+            .is_some()
+            .then(|| self.reconnector(cfg.pk.clone()))
+            .transpose()?;
+
+        Ok(WsClient::<S>::new_from_config(
+            stream,
+            cfg,
+            self.metrics.clone(),
+            Arc::clone(&self.clock),
+            self.decimal_places,
+            connector,
+        )
+        .await)
+    }
+
+    /// Waits for the first [`Transaction`] sent to `addr` after `since`, such as
+    /// a payment to a freshly created wallet.
+    ///
+    /// A websocket subscribed to all transactions is opened before `addr`'s
+    /// history is checked, so a payment made just before or during the call
+    /// isn't missed. If one is already in the history the oldest is returned
+    /// straight away, otherwise this waits up to `timeout` for one to arrive.
+    ///
+    /// # Errors
+    /// Errors with [`WebSocketError::TimeOut`] if nothing arrives within
+    /// `timeout`, or if `addr` does not exist or there is a network issue.
+    ///
+    /// See [`Error`] for more info
+    pub async fn await_first_incoming(
+        &self,
+        addr: &Address,
+        since: DateTime<Utc>,
+        timeout: Duration,
+    ) -> Result<Transaction, Error> {
+        let cfg = WsConfig::new().subscribe(SubscriptionType::Transactions);
+        let (ws, mut rx) = self.connnect_ws_config(cfg).await?;
+
+        let is_match = |tx: &Transaction| tx.to == *addr && tx.time > since;
 
-        let connector = Connector::Rustls(
-            ClientConfig::builder()
-                .with_root_certificates(root_store)
-                .with_no_client_auth()
-                .into(),
+        // History is newest first, so the last match is the earliest payment
+        let mut history = pin!(
+            self.all_wallet_transactions(addr, false)
+                .take_while(|res| future::ready(res.as_ref().map_or(true, |tx| tx.time > since)))
         );
 
-        let (stream, _) = connect_async_tls_with_config(url.as_str(), None, false, Some(connector))
+        let mut earliest = None;
+
+        while let Some(tx) = history.next().await {
+            let tx = tx?;
+
+            if is_match(&tx) {
+                earliest = Some(tx);
+            }
+        }
+
+        let res = match earliest {
+            Some(tx) => Ok(tx),
+            None => clock::timeout(self.clock.as_ref(), timeout, async {
+                while let Some(event) = rx.recv().await {
+                    if let WebSocketEvent::Transaction { transaction } = event
+                        && is_match(&transaction)
+                    {
+                        return Ok(transaction);
+                    }
+                }
+
+                Err(WebSocketError::RecvError.into())
+            })
             .await
-            .map_err(|err| WebSocketError::WsNetError {
-                source: Box::from(err),
-            })?;
+            .unwrap_or_else(|| Err(WebSocketError::TimeOut.into())),
+        };
+
+        let _ = ws.close().await;
+
+        res
+    }
+
+    /// Creates a [`Reconnector`] that starts a new session, authorized as `pk`
+    /// if given, and connects to it. It only holds what it needs from `self`
+    /// so it can outlive the client.
+    fn reconnector(&self, pk: Option<PrivateKey>) -> Result<Reconnector, Error> {
+        let start = self.url.join("/api/krist/ws/start").context(BadUrlSnafu)?;
+        let http = self.http.clone();
+        let tls = self.ws_tls.clone();
+
+        Ok(Arc::new(move || {
+            let (start, http, tls) = (start.clone(), http.clone(), tls.clone());
+            let body = WsConnBody {
+                privatekey: pk.clone(),
+            };
+
+            Box::pin(async move {
+                let url = http
+                    .post(start)
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(Response::error_for_status)
+                    .context(RequestFailedSnafu)?
+                    .json::<WsConnRes>()
+                    .await
+                    .context(RequestFailedSnafu)?
+                    .url;
+
+                connect(&url, tls).await
+            })
+        }))
+    }
+
+    /// Starts a websocket session without connecting to it, returning the URL
+    /// the socket can be opened on. If `pk` is provided, the session will be
+    /// authorized as its wallet.
+    ///
+    /// The URL is single use and expires if it isn't connected to shortly after
+    /// being issued, so don't cache it for long.
+    ///
+    /// # Errors
+    /// Errors if there is a network issue or `pk` is invalid
+    ///
+    /// See [`Error`] for more info
+    pub async fn ws_url(&self, pk: Option<PrivateKey>) -> Result<Url, Error> {
+        let start = Endpoint::post("/api/krist/ws/start", WsConnBody { privatekey: pk });
+
+        Ok(self.call::<WsConnRes, _, _, _>(start).await?.url)
+    }
+}
+
+async fn connect(url: &Url, tls: Arc<ClientConfig>) -> Result<crate::ws::KromerStream, Error> {
+    let connector = Connector::Rustls(tls);
+
+    let (stream, _) = connect_async_tls_with_config(url.as_str(), None, false, Some(connector))
+        .await
+        .context(WsNetSnafu)?;
+
+    Ok(stream)
+}
+
+/// Builds the TLS configuration websockets are opened with, so they follow the
+/// same settings as the [`Client`] they're opened from
+pub(super) fn tls_config(accept_invalid_certs: bool) -> Arc<ClientConfig> {
+    let builder = ClientConfig::builder();
+
+    let builder = if accept_invalid_certs {
+        let provider = builder.crypto_provider().clone();
+
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+    } else {
+        builder.with_root_certificates(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+        })
+    };
+
+    Arc::new(builder.with_no_client_auth())
+}
+
+/// Accepts any certificate, used when
+/// [`danger_accept_invalid_certs`](super::ClientBuilder::danger_accept_invalid_certs)
+/// is set. Handshake signatures are still checked.
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
 
-        Ok(WsClient::<S>::new_from_config(stream, &cfg.subscriptions).await)
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
     }
 }
 
@@ -35,7 +35,43 @@
 //! The websocket API also has support with the `websocket` feature flag. Functionally, it supports
 //! everything Kromer2 offers, with some caveats specified in its relevant [documentation](ws).
 //!
-//! The lookup API will be implemented once Kromer2 has merged support for more endpoints.
+//! Programs without an async runtime can enable the `blocking` feature for a
+//! synchronous client covering the common endpoints, see [`http::blocking`].
+//!
+//! Of the lookup API, fetching many addresses at once and listing an address'
+//! transactions are supported, see [`http::Client::lookup_addresses`] and
+//! [`http::Client::lookup_address_transactions`]. The rest will be implemented
+//! once Kromer2 has merged support for more endpoints.
+//!
+//! The `export` feature adds helpers for writing collections of models out as
+//! CSV or newline delimited JSON, see the [`export`] module.
+//!
+//! The `camel-case` feature makes models such as [`Wallet`](model::Wallet)
+//! serialize their fields in `camelCase`, for passing them straight through to
+//! frontends that expect it. Deserializing is unaffected.
+//!
+//! The `vanity` feature adds CPU heavy helpers for working with many keys at
+//! once, such as [`Address::from_keys`](model::Address::from_keys), and the
+//! [`vanity`] module for mining addresses with a chosen prefix.
+//!
+//! The `rand` feature adds [`PrivateKey::generate`](model::PrivateKey::generate)
+//! for creating random keys, such as for throwaway wallets. It is enabled by
+//! `vanity`.
+//!
+//! The `zeroize` feature overwrites the memory of a
+//! [`PrivateKey`](model::PrivateKey) when it is dropped, so keys don't linger on
+//! the heap in long running services.
+//!
+//! The `test-util` feature exposes constructors such as `Transaction::test`
+//! for building model fixtures in your own tests.
+//!
+//! The `location` feature makes [`Error`] record where in this crate it was
+//! created, which helps track down failures in larger applications.
+//!
+//! Anything that waits needs a timer, so retrying requests with
+//! `http::ClientBuilder::retry`, polling for new transactions with
+//! `http::Client::poll_new_transactions` and `clock::Clock::sleep` need the
+//! `tokio` feature. It is enabled by `websocket`.
 //!
 //! # Omissions
 //! There are some notable things that I've left out of this crate because they
@@ -56,13 +92,23 @@
 //! - Many Krist mining things - Kromer2 does not support earning currency through mining,
 //!   but provides many of the values and endpoints pertaining to it for the sake of
 //!   compatibility. We don't include this information. If you would still like to harm
-//!   the environment, you might consider vanity address mining.
+//!   the environment, you might consider vanity address mining, see the
+//!   `vanity` feature.
 //!
 //! [`Address::from`]: model::Address::from<model::PrivateKey>
 //! [Kromer2]: https://github.com/ReconnectedCC/kromer2
 
+pub mod clock;
+#[cfg(feature = "export")]
+pub mod export;
 pub mod http;
+pub mod metrics;
+#[cfg(test)]
+mod mock;
 pub mod model;
+pub mod stream;
+#[cfg(feature = "vanity")]
+pub mod vanity;
 
 #[cfg(feature = "websocket")]
 pub mod ws;
@@ -70,21 +116,76 @@ pub mod ws;
 use snafu::Snafu;
 
 /// Errors emitted by the `kromer_api` crate
+///
+/// With the `location` feature enabled, variants created by this crate (rather
+/// than passed through from another error type) also carry the
+/// [`Location`](snafu::Location) in the crate where they were created, which
+/// is shown in their `Debug` output.
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
 pub enum Error {
     #[snafu(display("couldn't parse provide string into URL"))]
-    BadUrl { source: url::ParseError },
+    BadUrl {
+        source: url::ParseError,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     /// Emitted when the underlying [`reqwest`] client can't build a request
     #[snafu(display("Failed to build request to"))]
-    BadRequest { source: reqwest::Error },
+    BadRequest {
+        source: reqwest::Error,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     /// Emitted when there is an issue parsing a `JSON` body received in a
-    /// response
+    /// response. Bodies are read in full, up to
+    /// [`max_response_bytes`](http::ClientBuilder::max_response_bytes), before
+    /// being parsed, so the source is a [`serde_json::Error`]. Failing to read
+    /// the body is a [`RequestFailed`](Self::RequestFailed) instead.
     #[snafu(display("Could not parse JSON body into response"))]
-    MalformedResponse { source: reqwest::Error },
+    MalformedResponse {
+        source: serde_json::Error,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
+    /// Emitted when a serialized [`PreparedTransaction`](model::krist::PreparedTransaction)
+    /// can't be parsed, see [`http::Client::submit_serialized`]
+    #[snafu(display("Could not parse prepared transaction"))]
+    BadPreparedTransaction {
+        source: serde_json::Error,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     /// Emitted when there is an issue communicating with the server itself
     #[snafu(display("Could not dispatch request"))]
-    RequestFailed { source: reqwest::Error },
+    RequestFailed {
+        source: reqwest::Error,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
+    /// Emitted when a response body is larger than the client's
+    /// [`max_response_bytes`](http::ClientBuilder::max_response_bytes)
+    #[snafu(display("Response body exceeded the limit of {limit} bytes"))]
+    ResponseTooLarge {
+        limit: usize,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
+    /// Emitted without sending the request when the client's
+    /// [`circuit_breaker`](http::ClientBuilder::circuit_breaker) is open
+    /// because the server appears to be down
+    #[snafu(display("Circuit breaker is open, not sending request"))]
+    CircuitOpen {
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     /// Issues parsing into models
     #[snafu(transparent)]
     ParseError { source: model::ParseError },
@@ -94,13 +195,184 @@ pub enum Error {
     /// Returned by the Krist API
     #[snafu(transparent)]
     KristResponse { source: model::krist::KristError },
+    /// Thrown when creating a [`http::Client`] with a
+    /// [`user_agent`](http::ClientBuilder::user_agent) that isn't a valid
+    /// header value
+    BadUserAgent {
+        source: reqwest::header::InvalidHeaderValue,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     #[cfg(feature = "internal")]
     /// Thrown when creating a [`http::Client`]
     BadInternalKey {
         source: reqwest::header::InvalidHeaderValue,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
     },
+    /// Errors thrown when writing exports. See [`export::ExportError`] for more info.
+    #[cfg(feature = "export")]
+    #[snafu(transparent)]
+    ExportError { source: export::ExportError },
     /// Errors thrown when working with websockets. See [`ws::WebSocketError`] for more info.
     #[cfg(feature = "websocket")]
     #[snafu(transparent)]
     WebsocketError { source: ws::WebSocketError },
 }
+
+impl Error {
+    /// Whether the request failed before the server could answer it, because
+    /// it couldn't be reached, the request timed out, or the client's
+    /// [`circuit_breaker`](http::ClientBuilder::circuit_breaker) is open.
+    /// Sending the same request again later may succeed.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::RequestFailed { source, .. } => source.is_connect() || source.is_timeout(),
+            Self::CircuitOpen { .. } => true,
+            #[cfg(feature = "websocket")]
+            Self::WebsocketError { source } => matches!(
+                source,
+                ws::WebSocketError::WsNetError { .. } | ws::WebSocketError::TimeOut
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether sending the same request again may succeed. This is true for
+    /// [transient](Self::is_transient) errors and for the server's internal
+    /// errors, [`KristError::InternalServerError`] and
+    /// [`KromerError::InternalServerError`].
+    ///
+    /// Errors about the request itself, such as
+    /// [`KristError::InsufficientBalance`], [`KristError::NameTaken`] or
+    /// [`KristError::SameWalletTransfer`], will fail the same way every time
+    /// and are not retryable.
+    ///
+    /// Note that a failed [`http::Client::make_transaction`] may still have
+    /// gone through, so check before sending it again.
+    ///
+    /// [`KristError::InternalServerError`]: model::krist::KristError::InternalServerError
+    /// [`KromerError::InternalServerError`]: model::KromerError::InternalServerError
+    /// [`KristError::InsufficientBalance`]: model::krist::KristError::InsufficientBalance
+    /// [`KristError::NameTaken`]: model::krist::KristError::NameTaken
+    /// [`KristError::SameWalletTransfer`]: model::krist::KristError::SameWalletTransfer
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient()
+            || matches!(
+                self,
+                Self::KristResponse {
+                    source: model::krist::KristError::InternalServerError { .. }
+                } | Self::KromerResponse {
+                    source: model::KromerError::InternalServerError { .. }
+                }
+            )
+    }
+
+    /// Whether the server rejected the [`PrivateKey`](model::PrivateKey) used
+    /// for the request
+    #[must_use]
+    pub const fn is_auth_error(&self) -> bool {
+        matches!(
+            self,
+            Self::KristResponse {
+                source: model::krist::KristError::AuthFailed
+            }
+        )
+    }
+}
+
+/// Entry points for the fuzz targets in `fuzz/`. Not part of the public API.
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzz {
+    /// Parses a Krist error response, which must never panic
+    pub fn parse_krist_error(error: &str, message: &str) {
+        let _ = crate::http::RawKristError {
+            error: error.to_string(),
+            message: message.to_string(),
+        }
+        .parse(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    // These fail to compile, rather than fail at runtime, if one of the core
+    // handles stops being shareable between tasks
+    const _: () = {
+        assert_send_sync::<crate::http::Client<crate::http::Basic>>();
+        #[cfg(feature = "internal")]
+        assert_send_sync::<crate::http::Client<crate::http::Priviliged>>();
+        #[cfg(feature = "websocket")]
+        assert_send_sync::<crate::ws::WsClient<crate::ws::Guest>>();
+        #[cfg(feature = "websocket")]
+        assert_send_sync::<crate::ws::WsClient<crate::ws::Auth>>();
+        assert_send_sync::<crate::Error>();
+    };
+
+    #[tokio::test]
+    async fn error_classification() {
+        use crate::{
+            CircuitOpenSnafu, Error,
+            model::{KromerError, krist::KristError},
+        };
+
+        // Nothing is listening once the listener is dropped
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client = crate::http::Client::new(format!("http://{addr}").as_str()).unwrap();
+        let unreachable = client.get_motd().await.unwrap_err();
+        assert!(unreachable.is_transient() && unreachable.is_retryable());
+
+        let circuit_open: Error = CircuitOpenSnafu.build();
+        assert!(circuit_open.is_transient() && circuit_open.is_retryable());
+
+        let message = String::new();
+        let retryable: [Error; 2] = [
+            KristError::InternalServerError {
+                message: message.clone(),
+                status: Some(500),
+            }
+            .into(),
+            KromerError::InternalServerError {
+                message: message.clone(),
+            }
+            .into(),
+        ];
+        for e in retryable {
+            assert!(!e.is_transient() && e.is_retryable() && !e.is_auth_error());
+        }
+
+        let name = String::new();
+        let logical: [Error; 5] = [
+            KristError::InsufficientBalance.into(),
+            KristError::NameTaken { name }.into(),
+            KristError::SameWalletTransfer.into(),
+            KristError::TransactionsDisabled.into(),
+            KromerError::WalletError { message }.into(),
+        ];
+        for e in logical {
+            assert!(!e.is_transient() && !e.is_retryable() && !e.is_auth_error());
+        }
+
+        let auth: Error = KristError::AuthFailed.into();
+        assert!(auth.is_auth_error() && !auth.is_retryable());
+
+        #[cfg(feature = "websocket")]
+        {
+            let timeout: Error = crate::ws::WebSocketError::TimeOut.into();
+            assert!(timeout.is_transient() && timeout.is_retryable());
+
+            let invalid: Error = crate::ws::WebSocketError::InvalidType.into();
+            assert!(!invalid.is_retryable());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Hooks for observing the traffic a client generates
+//!
+//! Implement [`Metrics`] and install it with
+//! [`ClientBuilder::metrics`](crate::http::ClientBuilder::metrics) to feed
+//! request counts, latencies, errors, and websocket traffic into your own
+//! monitoring. Every method has an empty default implementation, so only the
+//! events you care about need to be handled. When no hook is installed, the
+//! client skips these calls entirely.
+//!
+//! ```rust
+//! # use kromer_api::{metrics::Metrics, http::ClientBuilder};
+//! # use std::{sync::atomic::{AtomicUsize, Ordering}, time::Duration};
+//! #[derive(Debug, Default)]
+//! struct Counter(AtomicUsize);
+//!
+//! impl Metrics for Counter {
+//!     fn request_finished(&self, _path: &str, _status: Option<u16>, _latency: Duration) {
+//!         self.0.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let client = ClientBuilder::new()
+//!     .metrics(Counter::default())
+//!     .build("https://kromer.reconnected.cc");
+//! ```
+
+use crate::Error;
+use std::{fmt::Debug, time::Duration};
+
+/// Receives events about the requests a client makes. See the
+/// [module docs](self) for more info
+#[allow(unused_variables)]
+pub trait Metrics: Debug + Send + Sync {
+    /// Called right before an HTTP request to `path` is sent
+    fn request_started(&self, path: &str) {}
+
+    /// Called once an HTTP request to `path` is done. `status` is `None` if no
+    /// response was received.
+    ///
+    /// Requests are cancelled by dropping the future making them. This is still
+    /// called for cancelled requests, with a `status` of `None`, so every call
+    /// to [`Self::request_started`] is matched by one to this.
+    fn request_finished(&self, path: &str, status: Option<u16>, latency: Duration) {}
+
+    /// Called when a method on the HTTP client returns an error
+    fn error(&self, err: &Error) {}
+
+    /// Called when a websocket frame of `bytes` length is received
+    fn ws_message_received(&self, bytes: usize) {}
+
+    /// Called when a websocket frame of `bytes` length is sent
+    fn ws_message_sent(&self, bytes: usize) {}
+}
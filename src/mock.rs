@@ -0,0 +1,214 @@
+//! A mock Kromer2 server and response fixtures shared by the crate's tests
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// The body of a `/api/krist/motd` response
+pub const MOTD: &str = r#"{"ok":true,"motd":"hi","public_url":"localhost","public_ws_url":"localhost","transactions_enabled":true,"debug_mode":false,"package":{"name":"kromer","version":"0.1.0","author":"","licence":"GPL-3.0","repository":"","git_hash":""},"notice":""}"#;
+
+/// A transfer of 1 KRO from `kaaaaaaaaa` to `to`, as the server sends it
+pub fn transaction(id: u32, to: &str) -> String {
+    format!(
+        r#"{{"id":{id},"from":"kaaaaaaaaa","to":"{to}","value":1,"time":"2025-01-01T00:00:00Z","name":null,"metadata":"","sent_metaname":null,"sent_name":null,"type":"transfer"}}"#
+    )
+}
+
+/// The body of a `/api/krist/names/{name}` response, for `name` owned by
+/// `owner`
+pub fn name(name: &str, owner: &str) -> String {
+    format!(
+        r#"{{"ok":true,"name":{{"name":"{name}","owner":"{owner}","original_owner":"{owner}","registered":"2025-01-01T00:00:00Z","updated":null,"transferred":null}}}}"#
+    )
+}
+
+/// The body of a page of `transactions` out of `total`
+pub fn transaction_page(transactions: &[String], total: usize) -> String {
+    format!(
+        r#"{{"ok":true,"count":{},"total":{total},"transactions":[{}]}}"#,
+        transactions.len(),
+        transactions.join(",")
+    )
+}
+
+/// A raw HTTP response with `status`, such as `200 OK`, and a `JSON` `body`
+pub fn json(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// An HTTP server on a random local port, answering requests from another
+/// thread until it is dropped. Works with both the async and blocking clients.
+pub struct MockServer {
+    pub url: String,
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Answers each request with the raw response `respond` returns for its
+    /// target, such as `/api/krist/motd`. Returning `None` leaves the
+    /// connection open without answering.
+    pub fn new(mut respond: impl FnMut(&str) -> Option<String> + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let (log, stopped) = (requests.clone(), stop.clone());
+        std::thread::spawn(move || {
+            // Unanswered connections are kept here so they stay open
+            #[allow(clippy::collection_is_never_read)]
+            let mut held = Vec::new();
+
+            for conn in listener.incoming() {
+                if stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                let Ok(mut conn) = conn else { continue };
+                let Some(target) = read_request(&conn) else {
+                    continue;
+                };
+
+                log.lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(target.clone());
+
+                match respond(&target) {
+                    Some(res) => {
+                        let _ = conn.write_all(res.as_bytes());
+                    }
+                    None => held.push(conn),
+                }
+            }
+        });
+
+        Self {
+            url: format!("http://{addr}"),
+            addr,
+            requests,
+            stop,
+        }
+    }
+
+    /// Answers every request with `status` and a `JSON` `body`
+    pub fn respond(status: &'static str, body: impl Into<String>) -> Self {
+        let body = body.into();
+        Self::new(move |_| Some(json(status, &body)))
+    }
+
+    /// Answers requests with each of `responses` in turn, repeating the last
+    /// one once they run out
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub fn seq(responses: Vec<(&'static str, String)>) -> Self {
+        let mut responses = responses.into_iter().peekable();
+        let mut last = None;
+
+        Self::new(move |_| {
+            if responses.peek().is_some() {
+                last = responses.next();
+            }
+
+            last.as_ref().map(|(status, body)| json(status, body))
+        })
+    }
+
+    /// Accepts connections but never answers them
+    pub fn silent() -> Self {
+        Self::new(|_| None)
+    }
+
+    /// The targets of every request received so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// How many requests have been received so far
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub fn hits(&self) -> usize {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        // Wakes the server thread so it sees it has been stopped
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Reads a request from `conn`, returning its target
+fn read_request(conn: &TcpStream) -> Option<String> {
+    let mut reader = BufReader::new(conn);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let target = line.split(' ').nth(1)?.to_string();
+
+    let mut len = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            len = value.trim().parse().ok()?;
+        }
+    }
+
+    reader.read_exact(&mut vec![0; len]).ok()?;
+
+    Some(target)
+}
+
+/// Opens a websocket to a local server, which hands its end of the socket to
+/// `serve`
+#[cfg(feature = "websocket")]
+pub async fn websocket<F, Fut>(
+    serve: F,
+) -> (
+    crate::ws::KromerStream,
+    tokio::task::JoinHandle<Fut::Output>,
+)
+where
+    F: FnOnce(tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>) -> Fut + Send + 'static,
+    Fut: Future<Output: Send> + Send + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (conn, _) = listener.accept().await.unwrap();
+        serve(tokio_tungstenite::accept_async(conn).await.unwrap()).await
+    });
+
+    let (stream, _) = tokio_tungstenite::connect_async(format!("ws://{addr}"))
+        .await
+        .unwrap();
+
+    (stream, server)
+}
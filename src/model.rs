@@ -1,11 +1,16 @@
 //! Type models for interacting with the Kromer2 API
 
+pub use format::*;
+pub use identifier::*;
 pub use wallet::*;
 
 pub mod krist;
 #[cfg(feature = "websocket")]
 pub mod ws;
 
+pub(crate) mod decimal;
+mod format;
+mod identifier;
 mod wallet;
 
 use serde::Deserialize;
@@ -59,6 +64,16 @@ pub enum ParseError {
         /// The index of the input at which the wrong byte was found
         index: usize,
     },
+    /// Thrown when a private key contains bytes outside of `[0-9A-Za-z-_]`
+    #[snafu(display(
+        "expected an alphanumeric byte, '-', or '_' in private key, found {got} at index {index}"
+    ))]
+    InvalidKeyByte {
+        /// The byte found
+        got: u8,
+        /// The index of the input at which the wrong byte was found
+        index: usize,
+    },
     /// Input string did not fall in the range `1..=64`
     #[snafu(display("Names must be between 1 and 64 characters long, found {len}"))]
     LengthBounds { len: usize },
@@ -68,4 +83,119 @@ pub enum ParseError {
     /// When the input contains invalid characters
     #[snafu(display("Names support alphanumeric characters, '-', and '_'. Found '{c}'"))]
     InvalidChar { c: char },
+    /// When a [`Transaction`](krist::Transaction) is interpreted as a
+    /// [`NameOperation`](krist::NameOperation) but isn't one, or is missing the
+    /// name it operates on
+    #[snafu(display("Transaction {id} is not a name operation"))]
+    NotNameOperation { id: u32 },
+    /// When a [`Transaction`](krist::Transaction) in a page could not be
+    /// deserialized, see
+    /// [`Client::list_transactions_lenient`](crate::http::Client::list_transactions_lenient)
+    #[snafu(display("Transaction at index {index} of the page could not be parsed"))]
+    MalformedTransaction {
+        /// The index of the transaction within the page
+        index: usize,
+        source: serde_json::Error,
+    },
+}
+
+impl ParseError {
+    /// A message describing the error that is suitable for showing to end
+    /// users, such as beside a form field. Positions are counted from 1 and the
+    /// offending character is quoted.
+    ///
+    /// ```rust
+    /// # use kromer_api::model::Address;
+    /// let err = Address::parse(b"kaZaaaaaaa").unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.user_message(),
+    ///     "The 3rd character 'Z' isn't allowed in an address"
+    /// );
+    /// ```
+    ///
+    /// Use the [`Display`](std::fmt::Display) implementation for logs.
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            Self::UnexpectedLength { exp, got } => {
+                format!("An address must be {exp} characters long, but this is {got}")
+            }
+            Self::InvalidPrefix { got } => {
+                format!(
+                    "An address must start with 'k', not '{}'",
+                    got.escape_ascii()
+                )
+            }
+            Self::InvalidByte { got, index } => format!(
+                "The {} character '{}' isn't allowed in an address",
+                ordinal(index + 1),
+                got.escape_ascii()
+            ),
+            Self::InvalidKeyByte { got, index } => format!(
+                "The {} character '{}' isn't allowed in a private key",
+                ordinal(index + 1),
+                got.escape_ascii()
+            ),
+            Self::LengthBounds { len } => {
+                format!("A name must be between 1 and 64 characters long, but this is {len}")
+            }
+            Self::BadSuffix => format!(r#"A name can only end in ".{}""#, krist::NAME_SUFFIX),
+            Self::InvalidChar { c } => format!(
+                "The character '{c}' isn't allowed in a name, only letters, numbers, '-', and '_' are"
+            ),
+            Self::NotNameOperation { id } => {
+                format!("Transaction {id} isn't a name purchase, transfer, or update")
+            }
+            Self::MalformedTransaction { index, .. } => {
+                format!("The {} transaction couldn't be read", ordinal(index + 1))
+            }
+        }
+    }
+}
+
+/// Formats `n` as an English ordinal, such as "1st" or "12th"
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{n}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseError, ordinal};
+    use crate::model::krist::Name;
+
+    #[test]
+    fn ordinals() {
+        let got: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 111].map(ordinal).into();
+
+        assert_eq!(
+            got,
+            [
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "111th"
+            ]
+        );
+    }
+
+    #[test]
+    fn user_messages() {
+        let err = ParseError::InvalidPrefix { got: b'x' };
+        assert_eq!(
+            err.user_message(),
+            "An address must start with 'k', not 'x'"
+        );
+
+        let err = Name::parse("bad!name").unwrap_err();
+        assert_eq!(
+            err.user_message(),
+            "The character '!' isn't allowed in a name, only letters, numbers, '-', and '_' are"
+        );
+    }
 }
@@ -0,0 +1,136 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{
+    Deserializer,
+    de::{self, Visitor},
+};
+use std::{cell::Cell, fmt};
+
+thread_local! {
+    /// The number of decimal places [`string_or_number`] rounds to, if any.
+    /// Deserialization is synchronous, so setting this around a call to
+    /// `serde_json` applies it to exactly that call.
+    static PRECISION: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with every [`Decimal`] deserialized by [`string_or_number`]
+/// rounded to `dp` decimal places, or left as is if `dp` is `None`
+pub fn with_precision<T>(dp: Option<u32>, f: impl FnOnce() -> T) -> T {
+    /// Puts back the previous precision when dropped, so it is restored even
+    /// if `f` panics
+    struct Restore(Option<u32>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            PRECISION.set(self.0);
+        }
+    }
+
+    let _restore = Restore(PRECISION.replace(dp));
+
+    f()
+}
+
+/// Deserializes a [`Decimal`] encoded as either a `JSON` number or a string.
+///
+/// Some Krist compatible servers send amounts as strings. We don't rely on
+/// [`Decimal`]'s own implementation for this, as what it accepts changes with
+/// `rust_decimal`'s feature flags, which may be enabled by other crates.
+pub fn string_or_number<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = deserializer.deserialize_any(DecimalVisitor)?;
+
+    Ok(PRECISION.get().map_or(value, |dp| {
+        value.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero)
+    }))
+}
+
+struct DecimalVisitor;
+
+impl Visitor<'_> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal number or a string containing one")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Decimal, E> {
+        // Going through the shortest round-trip representation avoids picking
+        // up binary noise, e.g. `0.1` stays `0.1`
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Decimal, E> {
+        v.trim()
+            .parse::<Decimal>()
+            .or_else(|_| Decimal::from_scientific(v.trim()))
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Wallet, krist::Transaction};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn wallet_numeric_and_string() {
+        let numeric = r#"{"address":"kzzzzzzzzz","balance":12.5,"created_at":"2025-01-01T00:00:00Z","total_in":100,"total_out":87.5}"#;
+        let string = r#"{"address":"kzzzzzzzzz","balance":"12.5","created_at":"2025-01-01T00:00:00Z","total_in":"100","total_out":"87.5"}"#;
+
+        for json in [numeric, string] {
+            let wallet: Wallet = serde_json::from_str(json).unwrap();
+            assert_eq!(wallet.balance, Decimal::new(125, 1));
+            assert_eq!(wallet.total_in, Decimal::from(100));
+            assert_eq!(wallet.total_out, Decimal::new(875, 1));
+        }
+    }
+
+    #[test]
+    fn transaction_numeric_and_string() {
+        let template = crate::mock::transaction(1, "kbbbbbbbbb");
+
+        for value in ["0.1", r#""0.1""#] {
+            let json = template.replace(r#""value":1,"#, &format!(r#""value":{value},"#));
+            let tx: Transaction = serde_json::from_str(&json).unwrap();
+            assert_eq!(tx.value, Decimal::new(1, 1));
+        }
+    }
+
+    #[test]
+    fn rounds_to_precision() {
+        let json = r#"{"address":"kzzzzzzzzz","balance":1.1000000001,"created_at":"2025-01-01T00:00:00Z","total_in":"2.005","total_out":0}"#;
+
+        let wallet: Wallet = super::with_precision(Some(2), || serde_json::from_str(json)).unwrap();
+        assert_eq!(wallet.balance, Decimal::new(110, 2));
+        assert_eq!(wallet.total_in, Decimal::new(201, 2));
+
+        let wallet: Wallet = serde_json::from_str(json).unwrap();
+        assert_eq!(wallet.balance, Decimal::new(11_000_000_001, 10));
+    }
+
+    #[test]
+    fn precision_restored_after_panic() {
+        let res = std::panic::catch_unwind(|| {
+            super::with_precision(Some(2), || panic!("failed mid deserialize"));
+        });
+
+        assert!(res.is_err());
+        assert_eq!(super::PRECISION.get(), None);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let json = r#"{"address":"kzzzzzzzzz","balance":"lots","created_at":"2025-01-01T00:00:00Z","total_in":0,"total_out":0}"#;
+        assert!(serde_json::from_str::<Wallet>(json).is_err());
+    }
+}
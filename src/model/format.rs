@@ -0,0 +1,73 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// The symbol used by [`format_kromer`] when the server's currency is unknown
+pub const DEFAULT_CURRENCY_SYMBOL: &str = "KRO";
+
+/// Formats an amount of Kromer for display, e.g. `1,234.50 KRO`. The amount is
+/// rounded to two decimal places, away from zero on a midpoint.
+///
+/// If you have fetched the [`Motd`](crate::model::krist::Motd), prefer
+/// [`Currency::format`](crate::model::krist::Currency::format) so the symbol
+/// matches what the server advertises.
+///
+/// ```rust
+/// # use kromer_api::model::format_kromer;
+/// # use rust_decimal::Decimal;
+/// assert_eq!(format_kromer(Decimal::new(1_234_5, 1)), "1,234.50 KRO");
+/// ```
+#[must_use]
+pub fn format_kromer(amount: Decimal) -> String {
+    format_currency(amount, DEFAULT_CURRENCY_SYMBOL)
+}
+
+/// Formats an amount the same way as [`format_kromer`], but with a custom
+/// currency symbol
+#[must_use]
+pub fn format_currency(amount: Decimal, symbol: &str) -> String {
+    let rounded = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    let digits = format!("{:.2}", rounded.abs());
+
+    let (int, frac) = digits.split_once('.').unwrap_or((&digits, "00"));
+
+    let mut res = String::with_capacity(digits.len() + int.len() / 3 + symbol.len() + 2);
+
+    if rounded.is_sign_negative() && !rounded.is_zero() {
+        res.push('-');
+    }
+
+    for (i, c) in int.chars().enumerate() {
+        if i != 0 && (int.len() - i) % 3 == 0 {
+            res.push(',');
+        }
+        res.push(c);
+    }
+
+    res.push('.');
+    res.push_str(frac);
+    res.push(' ');
+    res.push_str(symbol);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_currency, format_kromer};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn format_groups_and_rounds() {
+        assert_eq!(format_kromer(Decimal::ZERO), "0.00 KRO");
+        assert_eq!(format_kromer(Decimal::new(5, 3)), "0.01 KRO");
+        assert_eq!(format_kromer(Decimal::new(999_995, 3)), "1,000.00 KRO");
+        assert_eq!(
+            format_kromer(Decimal::new(1_234_567_891, 2)),
+            "12,345,678.91 KRO"
+        );
+        assert_eq!(
+            format_kromer(Decimal::new(-1_000_001, 1)),
+            "-100,000.10 KRO"
+        );
+        assert_eq!(format_currency(Decimal::new(-1, 3), "KST"), "0.00 KST");
+    }
+}
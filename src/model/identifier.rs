@@ -0,0 +1,97 @@
+use super::Address;
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// Something that identifies one or more [`Wallets`](super::Wallet)
+///
+/// Either an [`Address`], a player's Minecraft `UUID`, or their username.
+/// Resolve one with [`Client::wallets_for`](crate::http::Client::wallets_for).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    /// A single wallet's [`Address`]
+    Address(Address),
+    /// The `UUID` of a Minecraft player
+    Uuid(Uuid),
+    /// The username of a Minecraft player
+    Username(String),
+}
+
+impl Identifier {
+    /// Classifies `s` as an [`Address`] if it parses as one, then as a `UUID`,
+    /// and falls back to a username otherwise.
+    ///
+    /// Minecraft usernames can be up to 16 characters of `[A-Za-z0-9_]`, so a
+    /// username such as `kabcdef123` or `serverwelf` is indistinguishable from
+    /// an address and will be classified as one. Construct
+    /// [`Identifier::Username`] directly if you know the input is a username.
+    ///
+    /// ```rust
+    /// # use kromer_api::model::{Address, Identifier};
+    /// assert_eq!(
+    ///     Identifier::parse("serverwelf"),
+    ///     Identifier::Address(Address::Serverwelf)
+    /// );
+    /// assert_eq!(
+    ///     Identifier::parse("Notch"),
+    ///     Identifier::Username("Notch".to_string())
+    /// );
+    /// ```
+    #[must_use]
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+
+        Address::parse(s.as_bytes()).map_or_else(
+            |_| Uuid::parse_str(s).map_or_else(|_| Self::Username(s.to_string()), Self::Uuid),
+            Self::Address,
+        )
+    }
+}
+
+impl From<Address> for Identifier {
+    fn from(value: Address) -> Self {
+        Self::Address(value)
+    }
+}
+
+impl From<Uuid> for Identifier {
+    fn from(value: Uuid) -> Self {
+        Self::Uuid(value)
+    }
+}
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(addr) => addr.fmt(f),
+            Self::Uuid(id) => id.fmt(f),
+            Self::Username(name) => f.write_str(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Identifier;
+    use crate::model::Address;
+    use uuid::Uuid;
+
+    #[test]
+    fn classify() {
+        assert_eq!(
+            Identifier::parse("kdk1ku9oeq"),
+            Identifier::Address(Address::parse(b"kdk1ku9oeq").unwrap())
+        );
+        assert_eq!(
+            Identifier::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5"),
+            Identifier::Uuid(Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap())
+        );
+        assert_eq!(
+            Identifier::parse(" Kdk1ku9oeq "),
+            Identifier::Username("Kdk1ku9oeq".to_string())
+        );
+        assert_eq!(
+            Identifier::parse("name"),
+            Identifier::Username("name".to_string())
+        );
+    }
+}
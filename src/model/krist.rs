@@ -1,13 +1,23 @@
 //! Types modeling the Krist compatible section of the Kromer2 API
 
-use super::Wallet;
-use serde::{Deserialize, Serialize};
+use super::{Wallet, format_currency};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
 use snafu::Snafu;
 use std::fmt::Debug;
 
+pub use meta::CommonMeta;
 pub use names::*;
 pub use transactions::*;
 
+/// There is only one address type in this crate, [`model::Address`](super::Address),
+/// which is used by both the Kromer and Krist sections of the API. It is
+/// re-exported here so code written against `model::krist::Address` works
+/// without any conversion.
+pub use super::Address;
+
+mod meta;
 mod names;
 mod transactions;
 
@@ -52,16 +62,27 @@ pub enum KristError {
     /// Various internal errors are exposed under the same name in the `error`
     /// field of the `JSON` response, but have different messages. We just pass
     /// the message up much we're able to to about it.
+    ///
+    /// Unrecognised error codes, such as a rate limit, end up here too, so
+    /// `status` holds the HTTP status code of the response when there was
+    /// one, e.g. `429` or `500`. It's `None` for errors sent over a websocket.
     #[snafu(display("Kromer2 server error: {message}"))]
-    InternalServerError { message: String },
+    InternalServerError {
+        message: String,
+        status: Option<u16>,
+    },
     #[snafu(display("Recieved an unexpected response"))]
     UnexpectedResponse,
 }
 
-/// Message of the day. `Currency` field is omitted since this doesn't change
+/// Message of the day
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
 pub struct Motd {
-    // pub server_time: DateTime<Utc>,
+    /// The server's clock when it sent this, for measuring clock skew. Older
+    /// servers don't send it, in which case it is `None`
+    #[serde(default)]
+    pub server_time: Option<DateTime<Utc>>,
     /// The message of the day
     #[serde(alias = "motd")]
     pub msg: String,
@@ -77,10 +98,37 @@ pub struct Motd {
     pub package: Package,
     /// An additional notice produced by the server
     pub notice: String,
+    /// The currency information advertised by the server, if it sent any
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+/// The currency section of the [`Motd`] `struct`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
+pub struct Currency {
+    /// The character addresses on this server start with
+    pub address_prefix: String,
+    /// The suffix names on this server end with, without the leading `'.'`
+    pub name_suffix: String,
+    /// The full name of the currency
+    pub currency_name: String,
+    /// The short symbol used when displaying amounts, e.g. `KRO`
+    pub currency_symbol: String,
+}
+
+impl Currency {
+    /// Formats `amount` for display using this currency's symbol. See
+    /// [`format_kromer`](crate::model::format_kromer) for the exact format
+    #[must_use]
+    pub fn format(&self, amount: Decimal) -> String {
+        format_currency(amount, &self.currency_symbol)
+    }
 }
 
 /// The package section of the [`Motd`] `struct`
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
 pub struct Package {
     /// The name of the package
     pub name: String,
@@ -98,14 +146,130 @@ pub struct Package {
     pub git_hash: String,
 }
 
-/// A page of wallets fetched from the Krist API
+/// A page of items fetched from a paginated endpoint of the Krist API.
+///
+/// Endpoints name the list of items after what they contain, e.g.
+/// `addresses` or `transactions`. Each of those names is accepted, so a new
+/// paginated endpoint only needs a `Page<T>` for its item type. Pages are
+/// serialized back under the name given by the item's [`PageItem::KEY`].
 #[derive(Debug, Deserialize, Clone)]
-pub struct WalletPage {
-    /// The wallets fetched
-    #[serde(rename = "addresses")]
-    pub wallets: Vec<Wallet>,
-    /// The number of wallets received
+pub struct Page<T> {
+    /// The number of items received in this page
     pub count: usize,
-    /// The total wallets that can be fetched
+    /// The total number of items that could be fetched from this endpoint
     pub total: usize,
+    /// The items fetched
+    #[serde(
+        alias = "addresses",
+        alias = "names",
+        alias = "transactions",
+        alias = "results"
+    )]
+    pub items: Vec<T>,
+}
+
+/// An item that the Krist API returns in a [`Page`]
+pub trait PageItem {
+    /// The key the list of items is stored under, e.g. `transactions`
+    const KEY: &'static str;
+}
+
+impl PageItem for Wallet {
+    const KEY: &'static str = "addresses";
+}
+
+impl PageItem for NameInfo {
+    const KEY: &'static str = "names";
+}
+
+impl PageItem for Transaction {
+    const KEY: &'static str = "transactions";
+}
+
+impl<T: PageItem + Serialize> Serialize for Page<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut page = serializer.serialize_struct("Page", 3)?;
+        page.serialize_field("count", &self.count)?;
+        page.serialize_field("total", &self.total)?;
+        page.serialize_field(T::KEY, &self.items)?;
+        page.end()
+    }
+}
+
+/// A page of wallets fetched from the Krist API
+pub type WalletPage = Page<Wallet>;
+
+impl WalletPage {
+    /// The wallets fetched
+    #[deprecated(note = "use the `items` field instead")]
+    #[must_use]
+    pub fn wallets(&self) -> &[Wallet] {
+        &self.items
+    }
+}
+
+impl NamePage {
+    /// The page of names
+    #[deprecated(note = "use the `items` field instead")]
+    #[must_use]
+    pub fn names(&self) -> &[NameInfo] {
+        &self.items
+    }
+}
+
+impl TransactionPage {
+    /// The transactions fetched
+    #[deprecated(note = "use the `items` field instead")]
+    #[must_use]
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Motd, Page, TransactionPage, WalletPage};
+    use crate::mock::MOTD;
+
+    #[test]
+    fn page_container_keys() {
+        let wallets = r#"{"count":1,"total":3,"addresses":[{"address":"kzzzzzzzzz","balance":1,"totalin":1,"totalout":0}]}"#;
+        let page: WalletPage = serde_json::from_str(wallets).unwrap();
+        assert_eq!(page.items[0].address.to_string(), "kzzzzzzzzz");
+        assert_eq!((page.count, page.total), (1, 3));
+
+        let results = r#"{"count":2,"total":2,"results":[1,2]}"#;
+        let page: Page<u32> = serde_json::from_str(results).unwrap();
+        assert_eq!(page.items, [1, 2]);
+    }
+
+    #[test]
+    fn page_serializes_container_key() {
+        let page = TransactionPage {
+            count: 0,
+            total: 0,
+            items: Vec::new(),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&page).unwrap(),
+            r#"{"count":0,"total":0,"transactions":[]}"#
+        );
+    }
+
+    #[test]
+    fn motd_server_time() {
+        let motd = |server_time: &str| -> Motd {
+            let json = MOTD.replacen(r#""motd""#, &format!(r#"{server_time}"motd""#), 1);
+            serde_json::from_str(&json).unwrap()
+        };
+
+        let with = motd(r#""server_time":"2025-06-01T12:30:00.123Z","#);
+        assert_eq!(
+            with.server_time.unwrap().to_rfc3339(),
+            "2025-06-01T12:30:00.123+00:00"
+        );
+
+        assert!(motd("").server_time.is_none());
+    }
 }
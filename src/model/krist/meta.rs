@@ -0,0 +1,212 @@
+use std::fmt::Display;
+
+/// Transaction metadata in the Krist `CommonMeta` format
+///
+/// This is a list of `;` separated entries. Most entries are `key=value`
+/// pairs, such as `return=kabcdefghi` or `useragent=kromer-api/0.1.0`, but
+/// plain values without a key are allowed too.
+///
+/// # Ordering
+/// Entries are always kept in the same order, no matter the order they were
+/// parsed or inserted in, so equal metadata always serializes to the same
+/// string and can be hashed or compared. Plain values come first in the order
+/// they were given, followed by the [well known keys](Self::WELL_KNOWN_KEYS) in
+/// the order listed there, followed by every other key sorted by byte value.
+///
+/// ```rust
+/// # use kromer_api::model::krist::CommonMeta;
+/// let meta = CommonMeta::parse("zzz=1;message=thanks;hello").insert("return", "kabcdefghi");
+///
+/// assert_eq!(meta.get("message"), Some("thanks"));
+/// assert_eq!(meta.to_string(), "hello;return=kabcdefghi;message=thanks;zzz=1");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommonMeta {
+    entries: Vec<(Option<String>, String)>,
+}
+
+impl CommonMeta {
+    /// Keys with a conventional meaning in Krist, which are ordered before any
+    /// other key
+    pub const WELL_KNOWN_KEYS: &[&str] = &["return", "username", "message", "error", "useragent"];
+
+    /// Creates empty metadata
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Parses metadata in the `CommonMeta` format. This never fails, anything
+    /// without an `=` is kept as a plain value and empty entries are skipped.
+    #[must_use]
+    pub fn parse(meta: &str) -> Self {
+        let mut entries: Vec<_> = meta
+            .split(';')
+            .filter(|e| !e.is_empty())
+            .map(|e| match e.split_once('=') {
+                Some((k, v)) => (Some(k.to_string()), v.to_string()),
+                None => (None, e.to_string()),
+            })
+            .collect();
+
+        // Stable, so plain values and repeated keys keep their relative order
+        entries.sort_by(|(a, _), (b, _)| rank(a.as_deref()).cmp(&rank(b.as_deref())));
+
+        Self { entries }
+    }
+
+    /// Sets `key` to `value`, replacing any existing value for `key`
+    #[must_use]
+    pub fn insert(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+
+        if let Some(v) = self.entry_mut(&key) {
+            *v = value;
+        } else {
+            let new = rank(Some(&key));
+            let at = self
+                .entries
+                .partition_point(|(k, _)| rank(k.as_deref()) <= new);
+
+            self.entries.insert(at, (Some(key), value));
+        }
+
+        self
+    }
+
+    /// Sets `key` to `value` only if `key` isn't already present, so values
+    /// supplied by the caller always win
+    #[must_use]
+    pub fn insert_default(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+
+        if self.get(&key).is_some() {
+            self
+        } else {
+            self.insert(key, value)
+        }
+    }
+
+    /// Appends `key=value` to the raw metadata `meta` if it doesn't already
+    /// have a `key` entry. Unlike [`Self::insert_default`] the rest of `meta`
+    /// is kept exactly as it was given, without reordering or dropping empty
+    /// entries.
+    pub(crate) fn append_default(meta: &str, key: &str, value: &str) -> String {
+        if Self::parse(meta).get(key).is_some() {
+            meta.to_string()
+        } else if meta.is_empty() || meta.ends_with(';') {
+            format!("{meta}{key}={value}")
+        } else {
+            format!("{meta};{key}={value}")
+        }
+    }
+
+    /// Gets the value for `key`, if there is one
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.as_deref() == Some(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns `true` if there are no entries
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn entry_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k.as_deref() == Some(key))
+            .map(|(_, v)| v)
+    }
+}
+
+/// Where an entry with `key` sorts, see [`CommonMeta`]'s ordering rules
+fn rank(key: Option<&str>) -> (usize, &str) {
+    let Some(key) = key else {
+        return (0, "");
+    };
+
+    CommonMeta::WELL_KNOWN_KEYS
+        .iter()
+        .position(|k| *k == key)
+        .map_or((CommonMeta::WELL_KNOWN_KEYS.len() + 1, key), |i| {
+            (i + 1, "")
+        })
+}
+
+impl Display for CommonMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (k, v)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+
+            match k {
+                Some(k) => write!(f, "{k}={v}")?,
+                None => f.write_str(v)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommonMeta;
+
+    #[test]
+    fn round_trip() {
+        let raw = "hello;return=kabcdefghi;message=a=b";
+        let meta = CommonMeta::parse(raw);
+
+        assert_eq!(meta.get("return"), Some("kabcdefghi"));
+        assert_eq!(meta.get("message"), Some("a=b"));
+        assert_eq!(meta.get("hello"), None);
+        assert_eq!(meta.to_string(), raw);
+        assert!(CommonMeta::parse(";;").is_empty());
+    }
+
+    #[test]
+    fn insert_default_keeps_existing() {
+        let meta = CommonMeta::parse("useragent=mine")
+            .insert_default("useragent", "other")
+            .insert_default("return", "kabcdefghi");
+
+        assert_eq!(meta.to_string(), "return=kabcdefghi;useragent=mine");
+    }
+
+    #[test]
+    fn append_default_keeps_raw() {
+        let append = |meta| CommonMeta::append_default(meta, "useragent", "x");
+
+        assert_eq!(append(""), "useragent=x");
+        assert_eq!(append("zzz=1;;message=hi"), "zzz=1;;message=hi;useragent=x");
+        assert_eq!(append("hello;"), "hello;useragent=x");
+        assert_eq!(append("b=2;useragent=mine;a=1"), "b=2;useragent=mine;a=1");
+    }
+
+    #[test]
+    fn stable_ordering() {
+        let a = CommonMeta::parse("b=2;hello;useragent=x;a=1;return=kabcdefghi;world");
+        let b = CommonMeta::new()
+            .insert("a", "1")
+            .insert("return", "kabcdefghi")
+            .insert("useragent", "x")
+            .insert("b", "2");
+
+        assert_eq!(
+            a.to_string(),
+            "hello;world;return=kabcdefghi;useragent=x;a=1;b=2"
+        );
+        assert_eq!(b.to_string(), "return=kabcdefghi;useragent=x;a=1;b=2");
+        assert_eq!(CommonMeta::parse(&a.to_string()), a);
+    }
+}
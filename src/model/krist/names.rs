@@ -1,16 +1,20 @@
-use crate::model::{Address, BadSuffixSnafu, InvalidCharSnafu, LengthBoundsSnafu, ParseError};
+use super::{Transaction, TransactionType};
+use crate::model::{
+    Address, BadSuffixSnafu, InvalidCharSnafu, LengthBoundsSnafu, NotNameOperationSnafu, ParseError,
+};
 use chrono::{DateTime, Utc};
 use serde::{
     Deserialize, Deserializer, Serialize,
     de::{Error as DeError, Visitor},
 };
-use snafu::ensure;
+use snafu::{OptionExt, Snafu, ensure};
 
 /// A name object fetched from the Kromer2 API.
 ///
 /// Does not include some fields defined in the Krist docs as these are
 /// irrelevant for Kromer
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
 pub struct NameInfo {
     /// The name, without the `.kro` suffix
     pub name: String,
@@ -27,6 +31,23 @@ pub struct NameInfo {
     pub transferred: Option<DateTime<Utc>>,
 }
 
+impl NameInfo {
+    /// Creates a name owned by its original purchaser for use in tests,
+    /// registered at the Unix epoch and never updated
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub fn test(name: &Name, owner: Address) -> Self {
+        Self {
+            name: name.to_string(),
+            owner,
+            original_owner: Some(owner),
+            registered: DateTime::UNIX_EPOCH,
+            updated: None,
+            transferred: None,
+        }
+    }
+}
+
 // begrudgingly heap allocate here because it actually makes sense. Though we do store it in a box
 // tto save an extra usize of space
 /// A name, stored without the `.kro` extension
@@ -43,14 +64,12 @@ impl Name {
     pub fn parse(s: &str) -> Result<Self, ParseError> {
         let kro_i = s.find(".kro");
 
-        let n_str: &str;
-
-        if let Some(i) = kro_i {
+        let n_str = if let Some(i) = kro_i {
             ensure!(s[i..] == *".kro", BadSuffixSnafu);
-            n_str = &s[..i];
+            &s[..i]
         } else {
-            n_str = s;
-        }
+            s
+        };
 
         ensure!(
             (1..=64).contains(&n_str.len()),
@@ -80,6 +99,170 @@ impl Name {
     }
 }
 
+impl Name {
+    /// Returns the name with the default [`NAME_SUFFIX`] appended, e.g.
+    /// `example.kro`
+    #[must_use]
+    pub fn with_suffix(&self) -> String {
+        self.display_suffixed().to_string()
+    }
+
+    /// Displays the name with the default [`NAME_SUFFIX`] appended, without
+    /// allocating
+    ///
+    /// ```rust
+    /// # use kromer_api::model::krist::Name;
+    /// let name = Name::parse("example").unwrap();
+    ///
+    /// assert_eq!(format!("{}", name.display_suffixed()), "example.kro");
+    /// ```
+    #[must_use]
+    pub const fn display_suffixed(&self) -> DisplaySuffixed<'_> {
+        self.display_with_suffix(NAME_SUFFIX)
+    }
+
+    /// Displays the name with a custom `suffix` appended, such as the
+    /// [`name_suffix`](super::Currency::name_suffix) a server advertises. The
+    /// suffix should not include the leading `'.'`
+    #[must_use]
+    pub const fn display_with_suffix<'a>(&'a self, suffix: &'a str) -> DisplaySuffixed<'a> {
+        DisplaySuffixed { name: self, suffix }
+    }
+}
+
+/// The suffix names on Kromer2 end with, without the leading `'.'`
+pub const NAME_SUFFIX: &str = "kro";
+
+/// Displays a [`Name`] along with its suffix. Created by
+/// [`Name::display_suffixed`] and [`Name::display_with_suffix`]
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySuffixed<'a> {
+    name: &'a Name,
+    suffix: &'a str,
+}
+
+impl std::fmt::Display for DisplaySuffixed<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.name, self.suffix)
+    }
+}
+
+impl Name {
+    /// Checks `self` against the default [`NamePolicy`], explaining why the
+    /// server would refuse to register it. Useful for validating a form
+    /// before contacting the server.
+    ///
+    /// # Errors
+    /// Returns the first [`NameRule`] the name breaks
+    pub fn is_registerable(&self) -> Result<(), NameRule> {
+        self.check(&NamePolicy::new())
+    }
+
+    /// Checks `self` against `policy`, see [`Self::is_registerable`]
+    ///
+    /// # Errors
+    /// Returns the first [`NameRule`] the name breaks
+    pub fn check(&self, policy: &NamePolicy) -> Result<(), NameRule> {
+        let name = self.inner();
+        let len = name.len();
+
+        ensure!(
+            len >= policy.min_len,
+            TooShortSnafu {
+                min: policy.min_len,
+                len
+            }
+        );
+        ensure!(
+            len <= policy.max_len,
+            TooLongSnafu {
+                max: policy.max_len,
+                len
+            }
+        );
+        ensure!(
+            policy.allow_numeric || !name.bytes().all(|b| b.is_ascii_digit()),
+            NumericSnafu
+        );
+        ensure!(
+            !policy.reserved.iter().any(|r| r.eq_ignore_ascii_case(name)),
+            ReservedSnafu
+        );
+
+        Ok(())
+    }
+}
+
+/// The rules a [`Name`] must follow to be registered, checked with
+/// [`Name::check`]
+///
+/// The defaults mirror what Kromer2 currently enforces: between 1 and 64
+/// characters, numeric names allowed, and nothing reserved. Adjust them as the
+/// server's policy changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamePolicy {
+    min_len: usize,
+    max_len: usize,
+    allow_numeric: bool,
+    reserved: Vec<String>,
+}
+
+impl NamePolicy {
+    /// Creates a new [`Self`] with the default rules
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            min_len: 1,
+            max_len: 64,
+            allow_numeric: true,
+            reserved: Vec::new(),
+        }
+    }
+
+    /// Sets the minimum and maximum length of a name, inclusive
+    #[must_use]
+    pub const fn length(mut self, min: usize, max: usize) -> Self {
+        self.min_len = min;
+        self.max_len = max;
+        self
+    }
+
+    /// Sets whether names made up only of digits are allowed
+    #[must_use]
+    pub const fn allow_numeric(mut self, v: bool) -> Self {
+        self.allow_numeric = v;
+        self
+    }
+
+    /// Reserves `name` so it can't be registered. Compared case-insensitively
+    /// and without the `.kro` suffix
+    #[must_use]
+    pub fn reserve(mut self, name: impl Into<String>) -> Self {
+        self.reserved.push(name.into());
+        self
+    }
+}
+
+impl Default for NamePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`NamePolicy`] rule broken by a [`Name`]
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum NameRule {
+    #[snafu(display("Names must be at least {min} characters long, found {len}"))]
+    TooShort { min: usize, len: usize },
+    #[snafu(display("Names must be at most {max} characters long, found {len}"))]
+    TooLong { max: usize, len: usize },
+    #[snafu(display("Names can't be made up of only numbers"))]
+    Numeric,
+    #[snafu(display("This name is reserved"))]
+    Reserved,
+}
+
 impl std::fmt::Display for Name {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.inner())
@@ -115,6 +298,14 @@ impl<'de> Deserialize<'de> for Name {
     }
 }
 
+impl std::str::FromStr for Name {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 impl TryFrom<&str> for Name {
     type Error = ParseError;
 
@@ -124,12 +315,202 @@ impl TryFrom<&str> for Name {
 }
 
 /// A paginated list of [`Names`](Name) fetched from the Kromer2 API
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NamePage {
-    /// The number of names received in this page
-    pub count: usize,
-    /// The total count of names
-    pub total: usize,
-    /// The page of names
-    pub names: Vec<NameInfo>,
+pub type NamePage = super::Page<NameInfo>;
+
+/// The result of registering a name. See
+/// [`Client::register_name`](crate::http::Client::register_name)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub enum RegisterOutcome {
+    /// The name was registered
+    Registered,
+    /// Someone else already owns the name
+    NameTaken,
+    /// The wallet can't afford the name
+    InsufficientFunds,
+}
+
+/// A name related action, interpreted from a [`Transaction`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameOperation {
+    /// A name was registered
+    Purchase {
+        /// The name that was registered
+        name: Name,
+        /// The address that registered the name
+        buyer: Address,
+    },
+    /// A name was transferred to a new owner
+    Transfer {
+        /// The name that was transferred
+        name: Name,
+        /// The previous owner of the name
+        from: Address,
+        /// The new owner of the name
+        to: Address,
+    },
+    /// A name's data (its A record) was changed
+    ARecord {
+        /// The name that was updated
+        name: Name,
+        /// The new data, or `None` if it was removed
+        data: Option<String>,
+    },
+}
+
+impl TryFrom<&Transaction> for NameOperation {
+    type Error = ParseError;
+
+    fn try_from(tx: &Transaction) -> Result<Self, Self::Error> {
+        let id = tx.id;
+
+        let name = || -> Result<Name, ParseError> {
+            Name::parse(tx.name.as_deref().context(NotNameOperationSnafu { id })?)
+        };
+
+        let from = || tx.from.context(NotNameOperationSnafu { id });
+
+        Ok(match tx.transaction_type {
+            TransactionType::NamePurchase => Self::Purchase {
+                name: name()?,
+                buyer: from()?,
+            },
+            TransactionType::NameTransfer => Self::Transfer {
+                name: name()?,
+                from: from()?,
+                to: tx.to.to_address().context(NotNameOperationSnafu { id })?,
+            },
+            TransactionType::NameARecord => Self::ARecord {
+                name: name()?,
+                data: tx.metadata.clone(),
+            },
+            TransactionType::Mined | TransactionType::Transfer => {
+                return NotNameOperationSnafu { id }.fail();
+            }
+        })
+    }
+}
+
+impl TryFrom<Transaction> for NameOperation {
+    type Error = ParseError;
+
+    fn try_from(tx: Transaction) -> Result<Self, Self::Error> {
+        Self::try_from(&tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Name, NameOperation, NamePolicy, NameRule};
+    use crate::model::{
+        Address, ParseError,
+        krist::{Transaction, TransactionType},
+    };
+    use rust_decimal::Decimal;
+
+    fn tx(kind: TransactionType, to: Address, metadata: Option<&str>) -> Transaction {
+        let from = Address::parse(b"kdk1ku9oeq").unwrap();
+
+        Transaction {
+            name: Some("example".to_string()),
+            metadata: metadata.map(ToString::to_string),
+            transaction_type: kind,
+            ..Transaction::test(7, Some(from), to, Decimal::ZERO)
+        }
+    }
+
+    #[test]
+    fn name_operations() {
+        let owner = Address::parse(b"kdk1ku9oeq").unwrap();
+        let other = Address::parse(b"ksg0aierdg").unwrap();
+        let name = Name::parse("example").unwrap();
+
+        let op = NameOperation::try_from(&tx(
+            TransactionType::NamePurchase,
+            Address::Serverwelf,
+            None,
+        ));
+        assert_eq!(
+            op.unwrap(),
+            NameOperation::Purchase {
+                name: name.clone(),
+                buyer: owner
+            }
+        );
+
+        let op = NameOperation::try_from(&tx(TransactionType::NameTransfer, other, None));
+        assert_eq!(
+            op.unwrap(),
+            NameOperation::Transfer {
+                name: name.clone(),
+                from: owner,
+                to: other
+            }
+        );
+
+        let op = NameOperation::try_from(&tx(
+            TransactionType::NameARecord,
+            Address::Serverwelf,
+            Some("https://example.com"),
+        ));
+        assert_eq!(
+            op.unwrap(),
+            NameOperation::ARecord {
+                name,
+                data: Some("https://example.com".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn not_name_operation() {
+        for kind in [TransactionType::Transfer, TransactionType::Mined] {
+            let res = NameOperation::try_from(&tx(kind, Address::Serverwelf, None));
+            assert!(matches!(res, Err(ParseError::NotNameOperation { id: 7 })));
+        }
+
+        let mut purchase = tx(TransactionType::NamePurchase, Address::Serverwelf, None);
+        purchase.name = None;
+        assert!(matches!(
+            NameOperation::try_from(purchase),
+            Err(ParseError::NotNameOperation { id: 7 })
+        ));
+    }
+
+    #[test]
+    fn from_str_round_trip() {
+        let name: Name = "example".parse().unwrap();
+        assert_eq!(name.to_string(), "example");
+
+        assert_eq!("example.kro".parse::<Name>().unwrap(), name);
+        assert!("bad!name".parse::<Name>().is_err());
+    }
+
+    #[test]
+    fn name_policy() {
+        let numeric = Name::parse("1234").unwrap();
+        let reserved = Name::parse("Admin").unwrap();
+
+        assert_eq!(numeric.is_registerable(), Ok(()));
+        assert_eq!(reserved.is_registerable(), Ok(()));
+
+        let policy = NamePolicy::new()
+            .length(2, 4)
+            .allow_numeric(false)
+            .reserve("admin");
+
+        assert_eq!(numeric.check(&policy), Err(NameRule::Numeric));
+        assert_eq!(
+            reserved.check(&policy),
+            Err(NameRule::TooLong { max: 4, len: 5 })
+        );
+        assert_eq!(
+            Name::parse("a").unwrap().check(&policy),
+            Err(NameRule::TooShort { min: 2, len: 1 })
+        );
+        assert_eq!(
+            reserved.check(&policy.length(1, 64)),
+            Err(NameRule::Reserved)
+        );
+    }
 }
@@ -1,24 +1,30 @@
-use super::Name;
-use crate::model::Address;
+use super::{KristError, NAME_SUFFIX, Name, SameWalletTransferSnafu};
+use crate::model::{Address, ParseError, PrivateKey};
 use chrono::DateTime;
 use chrono::Utc;
 use rust_decimal::Decimal;
-use serde::Deserializer;
+use serde::de::{Error as DeError, Visitor};
 use serde::{Deserialize, Serialize};
+use serde::{Deserializer, Serializer};
+use snafu::ensure;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 
 /// A Kromer2 transaction fetched from the API
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
 pub struct Transaction {
     /// The ID of this transaction
     pub id: u32,
     /// The address sending this transaction.
     pub from: Option<Address>,
-    /// The address receiving this transaction. Will be `"name"` if the
-    /// transaction was a name purchase, or `"a"` if it was a name's data
-    /// change.
-    pub to: Address,
+    /// Who received this transaction. Only transfers between wallets have a
+    /// real [`Address`] here, see [`TransactionTarget::to_address`].
+    pub to: TransactionTarget,
     /// The amount of Kromer transferred in this transaction. Can be 0, notably
     /// if the transaction was a name's data change.
+    #[serde(deserialize_with = "crate::model::decimal::string_or_number")]
     pub value: Decimal,
     /// The date and time this transaction was made.
     pub time: DateTime<Utc>,
@@ -40,6 +46,250 @@ pub struct Transaction {
     pub transaction_type: TransactionType,
 }
 
+impl Transaction {
+    /// Returns the other party involved in this transaction from the
+    /// perspective of `addr`, or `None` if `addr` was not involved or there is
+    /// no other party, as is the case for mined transactions, name purchases
+    /// and data changes.
+    #[must_use]
+    pub fn counterparty(&self, addr: &Address) -> Option<Address> {
+        if self.to == *addr {
+            self.from
+        } else if self.from == Some(*addr) {
+            self.to.to_address()
+        } else {
+            None
+        }
+    }
+
+    /// Whether this transaction was sent from
+    /// [`serverwelf`](Address::Serverwelf), minting Kromer into circulation
+    #[must_use]
+    pub fn is_mint(&self) -> bool {
+        self.from == Some(Address::Serverwelf)
+    }
+
+    /// Whether this transaction was sent to
+    /// [`serverwelf`](Address::Serverwelf), taking Kromer out of circulation
+    #[must_use]
+    pub fn is_burn(&self) -> bool {
+        self.to == Address::Serverwelf
+    }
+
+    /// Reconstructs the name this transaction was sent to as users write it,
+    /// e.g. `foo@bar.kro`, or `bar.kro` if there was no metaname. Returns
+    /// `None` if it wasn't sent to a name.
+    #[must_use]
+    pub fn full_recipient_name(&self) -> Option<String> {
+        self.full_recipient_name_with_suffix(NAME_SUFFIX)
+    }
+
+    /// Like [`Self::full_recipient_name`], but with a custom `suffix`, such as
+    /// the [`name_suffix`](super::Currency::name_suffix) a server advertises.
+    /// A metaname without a [`sent_name`](Self::sent_name) can't be addressed,
+    /// so this returns `None` in that case too.
+    #[must_use]
+    pub fn full_recipient_name_with_suffix(&self, suffix: &str) -> Option<String> {
+        let name = self.sent_name.as_ref()?.display_with_suffix(suffix);
+
+        Some(match self.sent_metaname.as_deref() {
+            Some(meta) if !meta.is_empty() => format!("{meta}@{name}"),
+            _ => name.to_string(),
+        })
+    }
+
+    /// Creates a transaction for use in tests. It is a `Mined` transaction if
+    /// `from` is `None`, and a `Transfer` otherwise, made at the Unix epoch
+    /// with no name or metadata.
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn test(id: u32, from: Option<Address>, to: Address, value: Decimal) -> Self {
+        Self {
+            id,
+            from,
+            to: TransactionTarget::Wallet(to),
+            value,
+            time: DateTime::UNIX_EPOCH,
+            name: None,
+            metadata: None,
+            sent_metaname: None,
+            sent_name: None,
+            transaction_type: if from.is_some() {
+                TransactionType::Transfer
+            } else {
+                TransactionType::Mined
+            },
+        }
+    }
+}
+
+/// Who a [`Transaction`] was sent to. Besides wallets, the API uses the
+/// strings `"name"` and `"a"` as the recipients of name purchases and name
+/// data changes, which this keeps apart from real addresses.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TransactionTarget {
+    /// A wallet, for transfers and name transfers
+    Wallet(Address),
+    /// The `"name"` recipient of a name purchase
+    NameRegistration,
+    /// The `"a"` recipient of a name's data change
+    DataChange,
+}
+
+impl TransactionTarget {
+    /// The [`Address`] of the receiving wallet, or `None` if the transaction
+    /// wasn't sent to one
+    #[must_use]
+    pub const fn to_address(&self) -> Option<Address> {
+        match self {
+            Self::Wallet(addr) => Some(*addr),
+            Self::NameRegistration | Self::DataChange => None,
+        }
+    }
+}
+
+impl From<Address> for TransactionTarget {
+    fn from(value: Address) -> Self {
+        Self::Wallet(value)
+    }
+}
+
+/// Only equal to an [`Address`] if the transaction was sent to that wallet
+impl PartialEq<Address> for TransactionTarget {
+    fn eq(&self, other: &Address) -> bool {
+        matches!(self, Self::Wallet(addr) if addr == other)
+    }
+}
+
+impl FromStr for TransactionTarget {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "name" => Self::NameRegistration,
+            "a" => Self::DataChange,
+            _ => Self::Wallet(Address::parse(s.as_bytes())?),
+        })
+    }
+}
+
+impl Display for TransactionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wallet(addr) => Display::fmt(addr, f),
+            Self::NameRegistration => f.write_str("name"),
+            Self::DataChange => f.write_str("a"),
+        }
+    }
+}
+
+impl Serialize for TransactionTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransactionTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TargetVisitor;
+
+        impl Visitor<'_> for TargetVisitor {
+            type Value = TransactionTarget;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(r#"wallet address, "name" or "a""#)
+            }
+
+            fn visit_str<E: DeError>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(TargetVisitor)
+    }
+}
+
+/// Totals of the transactions a wallet has made with a single counterparty.
+/// See [`Client::counterparty_summary`](crate::http::Client::counterparty_summary)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CounterpartyStats {
+    /// The number of transactions made with the counterparty, in either
+    /// direction
+    pub count: usize,
+    /// The total amount received from the counterparty
+    pub total_in: Decimal,
+    /// The total amount sent to the counterparty
+    pub total_out: Decimal,
+}
+
+/// A wallet's transactions totalled by counterparty. See
+/// [`Client::counterparty_summary`](crate::http::Client::counterparty_summary)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CounterpartySummary {
+    /// The stats for each counterparty
+    pub counterparties: HashMap<Address, CounterpartyStats>,
+    /// The total received from [`serverwelf`](Address::Serverwelf), if it was
+    /// folded out of `counterparties`
+    pub minted: Decimal,
+    /// The total sent to [`serverwelf`](Address::Serverwelf), if it was folded
+    /// out of `counterparties`
+    pub burned: Decimal,
+}
+
+impl CounterpartySummary {
+    /// Adds `tx` to the stats of its counterparty from the perspective of
+    /// `addr`. Transactions without a counterparty are ignored. If
+    /// `fold_serverwelf` is set, transactions with `serverwelf` are added to
+    /// [`minted`](Self::minted) or [`burned`](Self::burned) instead.
+    pub(crate) fn tally(&mut self, addr: &Address, tx: &Transaction, fold_serverwelf: bool) {
+        let Some(other) = tx.counterparty(addr) else {
+            return;
+        };
+
+        if fold_serverwelf && other == Address::Serverwelf {
+            if tx.is_mint() {
+                self.minted += tx.value;
+            } else {
+                self.burned += tx.value;
+            }
+
+            return;
+        }
+
+        let entry = self.counterparties.entry(other).or_default();
+        entry.count += 1;
+
+        if tx.to == *addr {
+            entry.total_in += tx.value;
+        } else {
+            entry.total_out += tx.value;
+        }
+    }
+}
+
+/// What sending a transaction will cost the sender. See
+/// [`Client::estimate_cost`](crate::http::Client::estimate_cost)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
+pub struct TransactionCost {
+    /// The amount received by the recipient
+    pub amount: Decimal,
+    /// The fee charged by the server on top of `amount`
+    pub fee: Decimal,
+    /// The total taken from the sender, `amount + fee`
+    pub total: Decimal,
+}
+
+impl TransactionCost {
+    pub(crate) fn new(amount: Decimal, fee: Decimal) -> Self {
+        Self {
+            amount,
+            fee,
+            total: amount + fee,
+        }
+    }
+}
+
 fn empty_string_is_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: Deserializer<'de>,
@@ -48,6 +298,63 @@ where
     if s.is_empty() { Ok(None) } else { Ok(Some(s)) }
 }
 
+/// A transaction that has been checked and is ready to be submitted with
+/// [`Client::submit`](crate::http::Client::submit).
+///
+/// Preparing a transaction doesn't need network access, so it can be done on
+/// an offline machine that holds the private key. The result serializes to the
+/// exact request body sent to the server, and can be stored or transferred as
+/// `JSON` and submitted later.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PreparedTransaction {
+    privatekey: PrivateKey,
+    to: Address,
+    amount: Decimal,
+    metadata: Option<String>,
+}
+
+impl PreparedTransaction {
+    /// Prepares a transaction sending `amount` Kromer to `addr` from the wallet
+    /// `pk` belongs to. Note that this does preform several expensive hashes to
+    /// convert `pk` into an [`Address`] to ensure it is not the same as `addr`
+    ///
+    /// # Errors
+    /// Errors if `pk` belongs to `addr`
+    pub fn new(
+        addr: &Address,
+        amount: Decimal,
+        meta: Option<&str>,
+        pk: PrivateKey,
+    ) -> Result<Self, KristError> {
+        ensure!(Address::from(&pk) != *addr, SameWalletTransferSnafu);
+
+        Ok(Self {
+            privatekey: pk,
+            to: *addr,
+            amount,
+            metadata: meta.map(ToString::to_string),
+        })
+    }
+
+    /// The [`Address`] the transaction is going to
+    #[must_use]
+    pub const fn to(&self) -> &Address {
+        &self.to
+    }
+
+    /// The amount of Kromer to send
+    #[must_use]
+    pub const fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// The metadata attached to the transaction
+    #[must_use]
+    pub fn metadata(&self) -> Option<&str> {
+        self.metadata.as_deref()
+    }
+}
+
 /// The type of a [`Transaction`]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
@@ -61,13 +368,114 @@ pub enum TransactionType {
 }
 
 /// A page of [`transactions`](Transaction) fetched from a paginated API
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct TransactionPage {
-    /// The number of transactions returned from this query
-    pub count: usize,
-    /// The total number of transactions that could ever be fetched from this
-    /// endpoint
-    pub total: usize,
-    /// The transactions fetched
-    pub transactions: Vec<Transaction>,
+pub type TransactionPage = super::Page<Transaction>;
+
+#[cfg(test)]
+mod tests {
+    use super::{CounterpartyStats, CounterpartySummary, Transaction, TransactionTarget};
+    use crate::{
+        mock,
+        model::{Address, krist::Name},
+    };
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn full_recipient_name() {
+        let mut tx = Transaction::test(1, None, Address::Serverwelf, Decimal::ONE);
+        assert_eq!(tx.full_recipient_name(), None);
+
+        tx.sent_metaname = Some("foo".to_string());
+        assert_eq!(tx.full_recipient_name(), None);
+
+        tx.sent_name = Some(Name::parse("bar").unwrap());
+        assert_eq!(tx.full_recipient_name().as_deref(), Some("foo@bar.kro"));
+        assert_eq!(
+            tx.full_recipient_name_with_suffix("kst").as_deref(),
+            Some("foo@bar.kst")
+        );
+
+        tx.sent_metaname = None;
+        assert_eq!(tx.full_recipient_name().as_deref(), Some("bar.kro"));
+    }
+
+    #[test]
+    fn transaction_targets() {
+        let tx = |to: &str, kind: &str| -> Transaction {
+            let mut json: serde_json::Value =
+                serde_json::from_str(&mock::transaction(1, to)).unwrap();
+            json["type"] = kind.into();
+            serde_json::from_value(json).unwrap()
+        };
+        let me = Address::parse(b"kaaaaaaaaa").unwrap();
+        let other = Address::parse(b"kbbbbbbbbb").unwrap();
+
+        let transfer = tx("kbbbbbbbbb", "transfer");
+        assert_eq!(transfer.to, TransactionTarget::Wallet(other));
+        assert_eq!(transfer.to.to_address(), Some(other));
+        assert_eq!(transfer.counterparty(&me), Some(other));
+
+        let purchase = tx("name", "name_purchase");
+        assert_eq!(purchase.to, TransactionTarget::NameRegistration);
+        assert_eq!(purchase.to.to_address(), None);
+        assert_eq!(purchase.counterparty(&me), None);
+
+        let data_change = tx("a", "name_a_record");
+        assert_eq!(data_change.to, TransactionTarget::DataChange);
+        assert_eq!(data_change.counterparty(&me), None);
+
+        // Serializes back to the same strings
+        let json = serde_json::to_value(&data_change).unwrap();
+        assert_eq!(json["to"], "a");
+    }
+
+    #[test]
+    fn tally_counterparties() {
+        let me = Address::parse(b"kaaaaaaaaa").unwrap();
+        let other = Address::parse(b"kbbbbbbbbb").unwrap();
+
+        let txs = [
+            Transaction::test(1, Some(other), me, Decimal::TEN),
+            Transaction::test(2, Some(me), other, Decimal::ONE),
+            Transaction::test(3, None, me, Decimal::TEN),
+        ];
+
+        let mut summary = CounterpartySummary::default();
+        for tx in &txs {
+            summary.tally(&me, tx, false);
+        }
+
+        assert_eq!(summary.counterparties.len(), 1);
+        assert_eq!(
+            summary.counterparties[&other],
+            CounterpartyStats {
+                count: 2,
+                total_in: Decimal::TEN,
+                total_out: Decimal::ONE,
+            }
+        );
+    }
+
+    #[test]
+    fn fold_serverwelf() {
+        let me = Address::parse(b"kaaaaaaaaa").unwrap();
+
+        let txs = [
+            Transaction::test(1, Some(Address::Serverwelf), me, Decimal::TEN),
+            Transaction::test(2, Some(me), Address::Serverwelf, Decimal::ONE),
+        ];
+        assert!(txs[0].is_mint() && txs[1].is_burn());
+
+        let mut plain = CounterpartySummary::default();
+        let mut folded = CounterpartySummary::default();
+        for tx in &txs {
+            plain.tally(&me, tx, false);
+            folded.tally(&me, tx, true);
+        }
+
+        assert_eq!(plain.counterparties[&Address::Serverwelf].count, 2);
+        assert_eq!(plain.minted, Decimal::ZERO);
+
+        assert!(folded.counterparties.is_empty());
+        assert_eq!((folded.minted, folded.burned), (Decimal::TEN, Decimal::ONE));
+    }
 }
@@ -1,16 +1,20 @@
-use super::ParseError;
+use super::{InvalidKeyByteSnafu, ParseError};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{
     Deserialize, Deserializer, Serialize,
     de::{Error as DeError, Visitor},
 };
-use sha2::{Digest, Sha256, digest::FixedOutput};
+use sha2::{Digest, Sha256, digest::Output};
+use snafu::ensure;
+use std::convert::Infallible;
 use std::fmt::Write;
 use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// An address for a [`Wallet`] on the Kromer API
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Hash)]
 pub enum Address {
     /// A normal user wallet in the format `^k[a-z0-9]{9}`
     Normal(AddressInner),
@@ -60,17 +64,101 @@ impl Address {
         }
     }
 
+    /// Writes the address out as text without allocating, for hot paths such
+    /// as logging or building URLs. The result derefs to a [`str`] matching
+    /// its [`Display`] output.
+    #[must_use]
+    pub fn as_arraystr(&self) -> AddressStr {
+        let mut buf = [0; 10];
+
+        match self {
+            Self::Normal(inner) => {
+                buf[0] = b'k';
+                buf[1..].copy_from_slice(&inner.0);
+            }
+            Self::Serverwelf => buf.copy_from_slice(b"serverwelf"),
+        }
+
+        AddressStr { buf }
+    }
+
+    /// Derives the [`Address`] of every key in `keys`, in the same order.
+    /// Each derivation is several rounds of SHA-256, so the work is split
+    /// across all available cores. Use [`Address::from`] for a single key.
+    #[cfg(feature = "vanity")]
+    #[must_use]
+    pub fn from_keys(keys: &[PrivateKey]) -> Vec<Self> {
+        let threads = std::thread::available_parallelism().map_or(1, std::num::NonZero::get);
+        let chunk_size = keys.len().div_ceil(threads).max(1);
+
+        std::thread::scope(|s| {
+            // Collected so every thread is spawned before the first join
+            #[allow(clippy::needless_collect)]
+            let handles: Vec<_> = keys
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(|| chunk.iter().map(Self::from).collect::<Vec<_>>()))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| {
+                    h.join()
+                        .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                })
+                .collect()
+        })
+    }
+
+    /// Finds the first key in `keys` that controls this address, such as when
+    /// recovering a wallet from several candidate keys.
+    ///
+    /// Keys are derived in parallel across all available cores, and keys after
+    /// a match aren't derived at all.
+    #[must_use]
+    pub fn select_key<'a>(&self, keys: &'a [PrivateKey]) -> Option<&'a PrivateKey> {
+        let threads = std::thread::available_parallelism().map_or(1, std::num::NonZero::get);
+        let chunk_size = keys.len().div_ceil(threads).max(1);
+
+        // The index of the earliest match so far
+        let best = AtomicUsize::new(usize::MAX);
+
+        std::thread::scope(|s| {
+            for (n, chunk) in keys.chunks(chunk_size).enumerate() {
+                let best = &best;
+
+                s.spawn(move || {
+                    for (i, key) in chunk.iter().enumerate() {
+                        let index = n * chunk_size + i;
+
+                        if index >= best.load(Ordering::Relaxed) {
+                            return;
+                        }
+
+                        if Self::from(key) == *self {
+                            best.fetch_min(index, Ordering::Relaxed);
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        keys.get(best.into_inner())
+    }
+
     fn parse_pk(pk: &str) -> Self {
         let mut protein = [0u8; 9];
         let mut used = [false; 9];
 
         let mut chain = [0u8; 9];
 
-        let mut hash = double_sha256(pk.as_bytes());
+        let mut hasher = PkHasher::new(pk.as_bytes());
+        hasher.sha256();
 
         for amino in &mut protein {
-            *amino = from_radix(&hash[0..=1]);
-            hash = double_sha256(&hash);
+            *amino = from_radix(&hasher.hex[0..=1]);
+            hasher.sha256();
+            hasher.sha256();
         }
 
         let mut i = 0;
@@ -78,10 +166,10 @@ impl Address {
         while i < 9 {
             let start = i * 2;
             let end = start + 2;
-            let index = (from_radix(&hash[start..end]) % 9) as usize;
+            let index = (from_radix(&hasher.hex[start..end]) % 9) as usize;
 
             if used[index] {
-                hash = sha256(&hash);
+                hasher.sha256();
             } else {
                 chain[i] = hex_to_base36(protein[index]);
                 used[index] = true;
@@ -168,6 +256,26 @@ impl From<&PrivateKey> for Address {
     }
 }
 
+impl From<&Wallet> for Address {
+    fn from(value: &Wallet) -> Self {
+        value.address
+    }
+}
+
+impl AsRef<Self> for Address {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl FromStr for Address {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s.as_bytes())
+    }
+}
+
 impl TryFrom<&[u8]> for Address {
     type Error = ParseError;
 
@@ -193,33 +301,115 @@ impl TryFrom<String> for Address {
 }
 
 #[doc(hidden)]
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Hash)]
 pub struct AddressInner([u8; 9]);
 
+/// An [`Address`] written out as text in a fixed size buffer, see
+/// [`Address::as_arraystr`]. Derefs to a [`str`].
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct AddressStr {
+    buf: [u8; 10],
+}
+
+impl AddressStr {
+    /// The address as a string slice
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        // Safety: The buffer is only ever filled from an `Address`, whose
+        // bytes being valid ASCII is one of our invariants
+        unsafe { std::str::from_utf8_unchecked(&self.buf) }
+    }
+}
+
+impl std::ops::Deref for AddressStr {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for AddressStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for AddressStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Debug for AddressStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
 /// A wallet fetched from the Kromer2 API. Does not include the ID field as
 /// there is little use for it and omitting it will allow the same type to be
 /// used for both the Kromer and Krist endpoints
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[cfg_attr(feature = "camel-case", serde(rename_all(serialize = "camelCase")))]
 pub struct Wallet {
     /// The [`Address`] associated with the wallet
     pub address: Address,
     /// The amount of Kromer in this wallet
+    #[serde(deserialize_with = "super::decimal::string_or_number")]
     pub balance: Decimal,
-    /// When this wallet was created
-    #[serde(alias = "firstseen")]
-    pub created_at: DateTime<Utc>,
+    /// When this wallet was created. Some endpoints return trimmed wallets
+    /// without this field, in which case it will be `None`
+    #[serde(alias = "firstseen", default)]
+    pub created_at: Option<DateTime<Utc>>,
     /// Whether this wallet is stopped from making transactions. If the API does
     /// not include this field, will default to `false`
     #[serde(default)]
     pub locked: bool,
     /// The total amount of Kromer that has been sent to this wallet
-    #[serde(alias = "totalin")]
+    #[serde(
+        alias = "totalin",
+        deserialize_with = "super::decimal::string_or_number"
+    )]
     pub total_in: Decimal,
     /// The total amount of Kromer that has been sent from this wallet
-    #[serde(alias = "totalout")]
+    #[serde(
+        alias = "totalout",
+        deserialize_with = "super::decimal::string_or_number"
+    )]
     pub total_out: Decimal,
 }
 
+impl Wallet {
+    /// The [`Address`] of this wallet, for chaining into address keyed calls
+    /// such as `wallets.iter().map(Wallet::addr)`
+    #[must_use]
+    pub const fn addr(&self) -> Address {
+        self.address
+    }
+
+    /// Creates an unlocked wallet for use in tests, created at the Unix epoch.
+    /// All of `balance` is counted as received.
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn test(address: Address, balance: Decimal) -> Self {
+        Self {
+            address,
+            balance,
+            created_at: Some(DateTime::UNIX_EPOCH),
+            locked: false,
+            total_in: balance,
+            total_out: Decimal::ZERO,
+        }
+    }
+}
+
+impl AsRef<Address> for Wallet {
+    fn as_ref(&self) -> &Address {
+        &self.address
+    }
+}
+
 /// A private key for a specific [`Address`]
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PrivateKey(Box<str>);
@@ -231,11 +421,58 @@ impl PrivateKey {
         Self(Box::from(val))
     }
 
+    /// Creates a [`PrivateKey`] from its raw bytes
+    ///
+    /// # Errors
+    /// Errors if any byte is not an ASCII alphanumeric character, `'-'`, or
+    /// `'_'`
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, ParseError> {
+        for (index, &got) in bytes.iter().enumerate() {
+            ensure!(
+                got.is_ascii_alphanumeric() || matches!(got, b'-' | b'_'),
+                InvalidKeyByteSnafu { got, index }
+            );
+        }
+
+        Ok(Self(bytes.iter().copied().map(char::from).collect()))
+    }
+
     /// Returns a reference to the underlying bytes
     #[must_use]
     pub const fn inner(&self) -> &str {
         &self.0
     }
+
+    /// Generates a random 32 character key, such as for a throwaway wallet.
+    /// Characters are drawn from a cryptographically secure RNG, out of those
+    /// accepted by [`Self::from_bytes`].
+    #[cfg(feature = "rand")]
+    #[must_use]
+    pub fn generate() -> Self {
+        use rand::RngExt;
+
+        const CHARSET: &[u8; 64] =
+            b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz-_";
+
+        let mut rng = rand::rng();
+
+        (0..32)
+            .map(|_| char::from(CHARSET[rng.random_range(..CHARSET.len())]))
+            .collect::<String>()
+            .as_str()
+            .into()
+    }
+
+    /// Like [`Self::generate`], but also derives the key's [`Address`], which
+    /// preforms several expensive hashes
+    #[cfg(feature = "rand")]
+    #[must_use]
+    pub fn generate_with_address() -> (Self, Address) {
+        let pk = Self::generate();
+        let addr = Address::from(&pk);
+
+        (pk, addr)
+    }
 }
 
 impl Display for PrivateKey {
@@ -244,36 +481,85 @@ impl Display for PrivateKey {
     }
 }
 
+/// Overwrites the key with zeroes, keeping its length
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for PrivateKey {}
+
+/// Any string is accepted as a private key, so this never fails
+impl FromStr for PrivateKey {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
 impl From<&str> for PrivateKey {
     fn from(value: &str) -> Self {
         Self::new(value)
     }
 }
 
-fn sha256(bytes: &[u8]) -> [u8; 64] {
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    encode_hex(hasher.finalize_fixed().into())
+/// Scratch buffers for deriving an [`Address`], reused for every round of
+/// hashing so that none of them allocate or copy arrays around
+struct PkHasher {
+    sha: Sha256,
+    digest: Output<Sha256>,
+    /// The hex encoding of the latest digest
+    hex: [u8; 64],
 }
 
-fn double_sha256(data: &[u8]) -> [u8; 64] {
-    sha256(&sha256(data))
+impl PkHasher {
+    /// Hashes `data` once
+    fn new(data: &[u8]) -> Self {
+        let mut res = Self {
+            sha: Sha256::new(),
+            digest: Output::<Sha256>::default(),
+            hex: [0; 64],
+        };
+
+        res.sha.update(data);
+        res.finish();
+
+        res
+    }
+
+    /// Replaces [`Self::hex`] with the hex encoded hash of itself
+    fn sha256(&mut self) {
+        self.sha.update(self.hex);
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        Digest::finalize_into_reset(&mut self.sha, &mut self.digest);
+        encode_hex(&self.digest, &mut self.hex);
+    }
 }
 
-fn encode_hex(bytes: [u8; 32]) -> [u8; 64] {
+fn encode_hex(bytes: &[u8], out: &mut [u8; 64]) {
     const HEX_VALS: [u8; 16] = [
         b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'a', b'b', b'c', b'd', b'e',
         b'f',
     ];
 
-    let mut res = [0u8; 64];
-
     for (i, b) in bytes.iter().enumerate() {
-        res[2 * i] = HEX_VALS[(b >> 4) as usize];
-        res[(2 * i) + 1] = HEX_VALS[(b & 0xf) as usize];
+        out[2 * i] = HEX_VALS[(b >> 4) as usize];
+        out[(2 * i) + 1] = HEX_VALS[(b & 0xf) as usize];
     }
-
-    res
 }
 
 fn decode_hex(byte: u8) -> u8 {
@@ -303,7 +589,8 @@ fn hex_to_base36(byte: u8) -> u8 {
 mod tests {
     use serde::{Deserialize, Serialize};
 
-    use super::{Address, PrivateKey};
+    use super::{Address, PrivateKey, Wallet};
+    use crate::model::ParseError;
 
     #[test]
     fn parse_pk() {
@@ -316,6 +603,22 @@ mod tests {
         assert_eq!(correct, maybe);
     }
 
+    #[test]
+    fn pk_from_bytes() {
+        let pk = PrivateKey::from_bytes(b"y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9").unwrap();
+        assert_eq!(pk.inner(), "y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9");
+
+        let mut bad = *b"y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9";
+        bad[4] = b'!';
+        assert!(matches!(
+            PrivateKey::from_bytes(&bad),
+            Err(ParseError::InvalidKeyByte {
+                got: b'!',
+                index: 4
+            })
+        ));
+    }
+
     #[derive(Debug, Deserialize, Serialize)]
     struct PkWrapper {
         pk: PrivateKey,
@@ -330,4 +633,111 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn wallet_without_created_at() {
+        let wallet: Wallet = serde_json::from_str(
+            r#"{"address":"kzzzzzzzzz","balance":1,"total_in":1,"total_out":0}"#,
+        )
+        .unwrap();
+
+        assert!(wallet.created_at.is_none());
+    }
+
+    #[cfg(feature = "camel-case")]
+    #[test]
+    fn camel_case_wallet() {
+        let wallet = Wallet::test(Address::Serverwelf, rust_decimal::Decimal::ONE);
+        let json = serde_json::to_value(wallet).unwrap();
+
+        assert!(json.get("totalIn").is_some());
+        assert!(json.get("createdAt").is_some());
+    }
+
+    #[test]
+    fn from_str_round_trip() {
+        for s in ["kdk1ku9oeq", "serverwelf"] {
+            assert_eq!(s.parse::<Address>().unwrap().to_string(), s);
+        }
+        assert!("kdk1ku9oe".parse::<Address>().is_err());
+        assert!("name".parse::<Address>().is_err());
+
+        let pk: PrivateKey = "y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9".parse().unwrap();
+        assert_eq!(pk.to_string(), "y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_clears_key() {
+        use zeroize::Zeroize;
+
+        let mut pk = PrivateKey::new("y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9");
+        let copy = pk.clone();
+        pk.zeroize();
+
+        assert_eq!(pk.inner(), "\0".repeat(32));
+        assert_eq!(copy.inner(), "y5HvW0g1wboIbLQaT6W3Wt8sT3f8tYO9");
+    }
+
+    #[test]
+    fn as_arraystr() {
+        let addrs = [Address::parse(b"kdk1ku9oeq").unwrap(), Address::Serverwelf];
+
+        for addr in addrs {
+            assert_eq!(&*addr.as_arraystr(), addr.to_string());
+        }
+    }
+
+    #[test]
+    fn hash_set_membership() {
+        use std::collections::HashSet;
+
+        let normal = Address::parse(b"kdk1ku9oeq").unwrap();
+        let set = HashSet::from([normal, Address::Serverwelf]);
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&normal));
+        assert!(set.contains(&Address::Serverwelf));
+        assert!(!set.contains(&Address::parse(b"kaaaaaaaaa").unwrap()));
+    }
+
+    #[test]
+    fn select_key() {
+        let keys: Vec<_> = (0..20)
+            .map(|i| PrivateKey::new(&format!("key-{}", i % 10)))
+            .collect();
+
+        let addr = Address::from(&keys[7]);
+        let found = addr.select_key(&keys).unwrap();
+        assert_eq!(keys.iter().position(|k| std::ptr::eq(k, found)), Some(7));
+
+        assert!(Address::Serverwelf.select_key(&keys).is_none());
+        assert!(addr.select_key(&[]).is_none());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn generate() {
+        let (pk, addr) = PrivateKey::generate_with_address();
+
+        let bytes: &[u8; 32] = pk.inner().as_bytes().try_into().unwrap();
+        assert_eq!(PrivateKey::from_bytes(bytes).unwrap(), pk);
+        assert_eq!(Address::from(&pk), addr);
+        assert_eq!(addr.to_string().parse::<Address>().unwrap(), addr);
+
+        assert_ne!(PrivateKey::generate(), pk);
+    }
+
+    #[cfg(feature = "vanity")]
+    #[test]
+    fn from_keys() {
+        let keys: Vec<_> = (0..50)
+            .map(|i| PrivateKey::new(&format!("key-{i}")))
+            .collect();
+
+        let expected: Vec<_> = keys.iter().map(Address::from).collect();
+
+        assert_eq!(Address::from_keys(&keys), expected);
+        assert!(Address::from_keys(&[]).is_empty());
+    }
 }
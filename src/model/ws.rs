@@ -28,3 +28,46 @@ pub enum SubscriptionType {
     /// from here. It is only included for deserialization purposes
     Blocks,
 }
+
+impl SubscriptionType {
+    /// Every [`SubscriptionType`] a client can subscribe to, in declaration
+    /// order. This includes [`Self::Blocks`], which is one of the
+    /// [`DEFAULT_SUBSCRIPTIONS`](crate::ws::DEFAULT_SUBSCRIPTIONS) even though
+    /// Kromer2 never sends any events for it.
+    pub const ALL: &'static [Self] = &[
+        Self::Transactions,
+        Self::OwnTransactions,
+        Self::Names,
+        Self::OwnNames,
+        Self::Blocks,
+    ];
+
+    /// Returns every [`SubscriptionType`]. See [`Self::ALL`]
+    #[must_use]
+    pub const fn all() -> &'static [Self] {
+        Self::ALL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubscriptionType;
+
+    #[test]
+    fn all_is_exhaustive() {
+        // Adding a variant breaks this match, which is a reminder to add it to
+        // the chain here and to `SubscriptionType::ALL` as well
+        let next = |sub| match sub {
+            SubscriptionType::Transactions => Some(SubscriptionType::OwnTransactions),
+            SubscriptionType::OwnTransactions => Some(SubscriptionType::Names),
+            SubscriptionType::Names => Some(SubscriptionType::OwnNames),
+            SubscriptionType::OwnNames => Some(SubscriptionType::Blocks),
+            SubscriptionType::Blocks => None,
+        };
+
+        let declared: Vec<_> =
+            std::iter::successors(Some(SubscriptionType::Transactions), |&sub| next(sub)).collect();
+
+        assert_eq!(SubscriptionType::all(), declared);
+    }
+}
@@ -0,0 +1,125 @@
+//! Adapters for the streams returned by this crate
+//!
+//! Combining a live feed, such as the websocket's transaction events, with a
+//! catch-up feed from polling the HTTP API will deliver some transactions more
+//! than once. [`DedupStream`] filters those repeats out.
+//!
+//! ```rust
+//! # use kromer_api::{Error, http::Client, model::Address, stream::DedupStream};
+//! # use futures_util::{StreamExt, stream};
+//! # async fn run(client: Client<kromer_api::http::Basic>, addr: Address) -> Result<(), Error> {
+//! let catch_up = client.all_wallet_transactions(&addr, false);
+//! let mut txs = DedupStream::new(catch_up, 1024);
+//!
+//! while let Some(tx) = txs.next().await {
+//!     println!("{}", tx?.id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, model::krist::Transaction};
+use futures_util::{Stream, StreamExt};
+use std::{
+    collections::{HashSet, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Wraps a stream of [`Transactions`](Transaction), skipping any whose
+/// [`id`](Transaction::id) was already yielded.
+///
+/// Only the ids of the most recent `capacity` transactions are remembered, so
+/// memory use stays flat no matter how long the stream runs. A repeat that
+/// arrives after more than `capacity` other transactions will get through.
+/// Errors are always passed on.
+pub struct DedupStream<S> {
+    inner: Pin<Box<S>>,
+    capacity: usize,
+    seen: HashSet<u32>,
+    order: VecDeque<u32>,
+}
+
+impl<S> DedupStream<S>
+where
+    S: Stream<Item = Result<Transaction, Error>>,
+{
+    /// Wraps `stream`, remembering up to `capacity` ids. A `capacity` of 0 is
+    /// treated as 1.
+    #[must_use]
+    pub fn new(stream: S, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            inner: Box::pin(stream),
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records `id`, returning `false` if it was already seen
+    fn remember(&mut self, id: u32) -> bool {
+        if !self.seen.insert(id) {
+            return false;
+        }
+
+        if self.order.len() == self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+
+        self.order.push_back(id);
+
+        true
+    }
+}
+
+impl<S> Stream for DedupStream<S>
+where
+    S: Stream<Item = Result<Transaction, Error>>,
+{
+    type Item = Result<Transaction, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(tx))) => {
+                    if self.remember(tx.id) {
+                        return Poll::Ready(Some(Ok(tx)));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DedupStream;
+    use crate::model::{Address, krist::Transaction};
+    use futures_util::{StreamExt, stream};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn skips_repeats() {
+        let items = [1, 2, 1, 3, 2, 4, 1].map(|id| {
+            Ok(Transaction::test(
+                id,
+                None,
+                Address::Serverwelf,
+                Decimal::ONE,
+            ))
+        });
+
+        let ids: Vec<_> = DedupStream::new(stream::iter(items), 2)
+            .map(|tx| tx.unwrap().id)
+            .collect()
+            .await;
+
+        // 1 has been forgotten by the time it repeats the second time
+        assert_eq!(ids, [1, 2, 3, 4, 1]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Mining vanity addresses, i.e. generating random private keys until one
+//! derives an address that starts with a chosen prefix
+//!
+//! Every character of a prefix makes it roughly 36 times harder to find, so
+//! anything beyond 4 or 5 characters is likely to take a very long time.
+//!
+//! ```rust
+//! # use kromer_api::vanity::{self, CancelToken};
+//! let cancel = CancelToken::new();
+//! let (pk, addr) = vanity::mine("a", 2, &cancel).unwrap().unwrap();
+//!
+//! assert!(addr.to_string().starts_with("ka"));
+//! # assert_eq!(kromer_api::model::Address::from(&pk), addr);
+//! ```
+
+use crate::model::{Address, ParseError, PrivateKey};
+use std::sync::{
+    Arc, OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Stops a call to [`mine`] early. Clones share the same state, so one can be
+/// moved to another thread and cancelled from there.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that hasn't been cancelled
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops every [`mine`] call using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`Self::cancel`] has been called
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Generates random private keys on `threads` worker threads until one derives
+/// an [`Address`] that starts with `prefix` after its leading `k`. Returns
+/// `None` if `cancel` is cancelled first.
+///
+/// Keys are generated with [`PrivateKey::generate`].
+///
+/// # Errors
+/// Errors if `prefix` is longer than an address or contains anything other
+/// than `0-9` and `a-z`, as no address could match it. Indexes in the error
+/// count the leading `k`, matching [`Address::parse`].
+pub fn mine(
+    prefix: &str,
+    threads: usize,
+    cancel: &CancelToken,
+) -> Result<Option<(PrivateKey, Address)>, ParseError> {
+    validate(prefix)?;
+
+    let found = OnceLock::new();
+
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| {
+                while found.get().is_none() && !cancel.is_cancelled() {
+                    let (pk, addr) = PrivateKey::generate_with_address();
+
+                    if addr.to_string()[1..].starts_with(prefix) {
+                        let _ = found.set((pk, addr));
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(found.into_inner())
+}
+
+fn validate(prefix: &str) -> Result<(), ParseError> {
+    if prefix.len() > 9 {
+        return Err(ParseError::UnexpectedLength {
+            exp: 9,
+            got: prefix.len(),
+        });
+    }
+
+    match prefix
+        .bytes()
+        .enumerate()
+        .find(|(_, b)| !matches!(b, b'0'..=b'9' | b'a'..=b'z'))
+    {
+        Some((i, got)) => Err(ParseError::InvalidByte { got, index: i + 1 }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancelToken, mine};
+    use crate::model::{Address, ParseError};
+
+    #[test]
+    fn mines_short_prefix() {
+        let (pk, addr) = mine("z", 2, &CancelToken::new()).unwrap().unwrap();
+
+        assert!(addr.to_string().starts_with("kz"));
+        assert_eq!(Address::from(&pk), addr);
+    }
+
+    #[test]
+    fn rejects_bad_prefix_and_cancels() {
+        assert!(matches!(
+            mine("aB", 1, &CancelToken::new()),
+            Err(ParseError::InvalidByte {
+                got: b'B',
+                index: 2
+            })
+        ));
+        assert!(matches!(
+            mine("aaaaaaaaaa", 1, &CancelToken::new()),
+            Err(ParseError::UnexpectedLength { got: 10, .. })
+        ));
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert!(mine("zzzzzzzzz", 4, &cancel).unwrap().is_none());
+    }
+}
@@ -26,17 +26,32 @@
 //!
 //! By creating a socket with a [`WsConfig`] that's had the [`WsConfig::with_auth`] method called
 //! on it, you will receive an [`Auth`] client with additional capabilities.
+//!
+//! # History
+//! The websocket protocol has no request for listing transactions, only for looking up wallets,
+//! making transactions, and managing subscriptions. Fetching a wallet's past transactions needs the
+//! HTTP [`Client`](crate::http::Client), e.g.
+//! [`Client::recent_wallet_transactions`](crate::http::Client::recent_wallet_transactions). The
+//! websocket only delivers transactions made after it subscribes, see
+//! [`SubscriptionType::Transactions`].
 
 use crate::{
     Error,
+    clock::{self, Clock},
     http::RawKristError,
+    metrics::Metrics,
     model::{
         Address, PrivateKey, Wallet,
-        krist::{SameWalletTransferSnafu, Transaction},
+        krist::{Name, NameInfo, SameWalletTransferSnafu, Transaction},
         ws::{SubscriptionType, WebSocketEvent},
     },
 };
-use futures_util::{SinkExt, StreamExt, stream::SplitSink};
+use arc_swap::ArcSwapOption;
+use chrono::{DateTime, Utc};
+use futures_util::{
+    SinkExt, Stream, StreamExt, future,
+    stream::{self, SplitSink, SplitStream},
+};
 use messages::{
     MessageResponseInner, WebSocketMessageInner, WebSocketRequest, WebSocketRequestInner,
 };
@@ -47,31 +62,53 @@ use snafu::{ResultExt, ensure};
 use std::{
     marker::PhantomData,
     sync::{
-        Arc,
-        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex, PoisonError,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     time::Duration,
 };
 use tokio::{
     net::TcpStream,
-    sync::{Mutex, mpsc::Receiver, oneshot},
-    time::timeout,
+    sync::{
+        Mutex, Notify,
+        broadcast::{self, error::RecvError},
+        mpsc::Receiver,
+        oneshot, watch,
+    },
+    task::AbortHandle,
 };
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
-use tracing::{debug, error as terror, instrument, trace};
+use tracing::{debug, error as terror, instrument, trace, warn};
 
 pub use error::*;
+pub use reconnect::{ConnectionStatus, ReconnectPolicy};
+
+/// The events Kromer2 subscribes every new socket to: [`Blocks`] and
+/// [`OwnTransactions`].
+///
+/// A [`WsConfig`] unsubscribes from whichever of these it
+/// wasn't asked to [`subscribe`](WsConfig::subscribe) to, unless
+/// [`WsConfig::keep_default_subscriptions`] is set.
+///
+/// [`Blocks`]: SubscriptionType::Blocks
+/// [`OwnTransactions`]: SubscriptionType::OwnTransactions
+pub const DEFAULT_SUBSCRIPTIONS: [SubscriptionType; 2] =
+    [SubscriptionType::Blocks, SubscriptionType::OwnTransactions];
 
 mod error;
 mod handle;
 mod messages;
+mod reconnect;
+
+pub(crate) use reconnect::Reconnector;
+use reconnect::Socket;
 
 // Only reason we don't automatically impl WsState for all who have WsStateSealed is so that
 // implementors will appear in docs
 /// Designates the current state of the socket.
 /// See its  for more info
 #[allow(private_bounds)]
-pub trait WsState: WsStateSealed + Send + Sync {}
+pub trait WsState: WsStateSealed + Send + Sync + 'static {}
 
 pub(super) trait WsStateSealed {}
 
@@ -86,17 +123,132 @@ impl WsStateSealed for Auth {}
 impl WsState for Auth {}
 
 /// A client for the Kromer2 websocket API
+///
+/// Like the HTTP [`Client`](crate::http::Client), every `WsClient` is
+/// `Send + Sync + 'static` and can be shared behind an [`Arc`].
+///
+/// Prefer [`Self::close`] when you are done with a client, but dropping it
+/// also tears down the socket and its background tasks.
 #[allow(dead_code)]
 pub struct WsClient<M: WsState> {
     pending_reqs: Arc<HashMap<usize, oneshot::Sender<WebSocketMessageInner>>>,
     /// The current message counter
-    n: AtomicUsize,
+    n: Arc<AtomicUsize>,
     tx: Arc<Mutex<SplitSink<KromerStream, Message>>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    /// The clock request timeouts, idle timeouts and reconnect delays run on,
+    /// shared with the [`Client`](crate::http::Client) that opened the socket
+    clock: Arc<dyn Clock>,
+    /// Amounts in incoming frames are rounded to this many decimal places, as
+    /// set by [`ClientBuilder::round_decimals`](crate::http::ClientBuilder::round_decimals)
+    decimal_places: Option<u32>,
+    /// The last subscription level reported by the server
+    subscriptions: Arc<StdMutex<Vec<SubscriptionType>>>,
+    /// The server time sent with the last keepalive
+    last_keepalive: Arc<ArcSwapOption<DateTime<Utc>>>,
+    /// Who the socket is authorized as, always set for [`Auth`] clients
+    login: Option<LoginInfo>,
+    /// The authorized wallet as it was when the socket was opened
+    initial_wallet: Option<Wallet>,
+    /// Notified whenever a request is sent or a message is received, if an idle
+    /// timeout was set
+    activity: Option<Arc<Notify>>,
+    /// Events are also sent here for the streams returned by methods like
+    /// [`WsClient::watch_name`]
+    events: broadcast::Sender<WebSocketEvent>,
+    /// Set once the socket is closed on purpose, so it isn't reconnected
+    closing: Arc<AtomicBool>,
+    status: watch::Receiver<ConnectionStatus>,
+    /// Background tasks that are aborted when the client is dropped
+    tasks: Vec<AbortHandle>,
 
     _marker: PhantomData<M>,
 }
 
-type KromerStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+pub(crate) type KromerStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How many events can be buffered for streams like [`WsClient::watch_name`]
+/// before the oldest are skipped
+const EVENT_BROADCAST_CAPACITY: usize = 64;
+
+/// Turns a broadcast receiver into a stream that ends when the socket closes
+fn events(rx: broadcast::Receiver<WebSocketEvent>) -> impl Stream<Item = WebSocketEvent> {
+    stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((event, rx)),
+                Err(RecvError::Lagged(n)) => warn!("event stream skipped {n} events"),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Closes the socket once `activity` hasn't been notified for `timeout`. See
+/// [`WsConfig::idle_timeout`]
+async fn close_when_idle(
+    timeout: Duration,
+    activity: Arc<Notify>,
+    clock: Arc<dyn Clock>,
+    tx: Arc<Mutex<SplitSink<KromerStream, Message>>>,
+    closing: Arc<AtomicBool>,
+) {
+    loop {
+        tokio::select! {
+            () = activity.notified() => (),
+            () = clock.sleep(timeout) => break,
+        }
+    }
+
+    debug!("closing idle socket");
+    closing.store(true, Ordering::Relaxed);
+
+    let mut tx = tx.lock().await;
+    let _ = tx.send(Message::Close(None)).await;
+    let _ = tx.close().await;
+
+    drop(tx);
+}
+
+/// Shows the marker, who the socket is authorized as and the connection
+/// status, e.g. `WsClient<Guest> { login: None, .. }`, leaving out the socket
+/// and other internals
+impl<M: WsState> std::fmt::Debug for WsClient<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("WsClient<{}>", crate::http::marker_name::<M>()))
+            .field("login", &self.login)
+            .field("status", &*self.status.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Dropping a client without calling [`WsClient::close`] stops its background
+/// tasks, ending the event [`Receiver`] and any streams, and sends the server
+/// a close frame on a best-effort basis. The close frame can only be sent if
+/// the client is dropped inside a Tokio runtime.
+impl<M: WsState> Drop for WsClient<M> {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+
+        if self.closing.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let tx = self.tx.clone();
+
+            runtime.spawn(async move {
+                let mut tx = tx.lock().await;
+                let _ = tx.send(Message::Close(None)).await;
+                let _ = tx.close().await;
+
+                drop(tx);
+            });
+        }
+    }
+}
 
 impl<M: WsState> WsClient<M> {
     /// Closes the underlying socket
@@ -105,6 +257,7 @@ impl<M: WsState> WsClient<M> {
     /// Errors if the socket decides not to close for god knows what reason
     pub async fn close(self) -> Result<(), WebSocketError> {
         debug!("closing socket");
+        self.closing.store(true, Ordering::Relaxed);
 
         let mut tx = self.tx.lock().await;
 
@@ -118,19 +271,36 @@ impl<M: WsState> WsClient<M> {
     }
 
     #[instrument(skip_all)]
-    pub(crate) async fn new(stream: KromerStream) -> (Self, Receiver<WebSocketEvent>) {
+    pub(crate) async fn new(
+        stream: KromerStream,
+        metrics: Option<Arc<dyn Metrics>>,
+        clock: Arc<dyn Clock>,
+        decimal_places: Option<u32>,
+    ) -> (Self, Receiver<WebSocketEvent>) {
         let (tx, rx) = stream.split();
 
-        let res = Self {
+        let (status_tx, status) = watch::channel(ConnectionStatus::Connected);
+
+        let mut res = Self {
             tx: Arc::new(Mutex::new(tx)),
-            n: AtomicUsize::default(),
+            n: Arc::default(),
             pending_reqs: Arc::default(),
+            metrics,
+            clock,
+            decimal_places,
+            subscriptions: Arc::new(StdMutex::new(DEFAULT_SUBSCRIPTIONS.to_vec())),
+            last_keepalive: Arc::default(),
+            login: None,
+            initial_wallet: None,
+            activity: None,
+            events: broadcast::channel(EVENT_BROADCAST_CAPACITY).0,
+            closing: Arc::default(),
+            status,
+            tasks: Vec::new(),
             _marker: PhantomData,
         };
 
-        let (send, recv) = tokio::sync::mpsc::channel(20);
-
-        tokio::spawn(handle::handle_incoming(rx, res.pending_reqs.clone(), send));
+        let recv = res.spawn_incoming(rx, None, None, status_tx);
 
         let _ = tokio::join!(
             res.unsubscribe(SubscriptionType::Blocks),
@@ -144,34 +314,205 @@ impl<M: WsState> WsClient<M> {
     #[instrument(skip_all)]
     pub(crate) async fn new_from_config(
         stream: KromerStream,
-        subs: &[SubscriptionType],
+        cfg: WsConfig<M>,
+        metrics: Option<Arc<dyn Metrics>>,
+        clock: Arc<dyn Clock>,
+        decimal_places: Option<u32>,
+        connector: Option<Reconnector>,
     ) -> (Self, Receiver<WebSocketEvent>) {
-        let default_events = [SubscriptionType::Blocks, SubscriptionType::OwnTransactions];
+        let default_events = DEFAULT_SUBSCRIPTIONS;
+        let subs = &cfg.subscriptions;
+
+        let login = cfg.pk.as_ref().map(|pk| LoginInfo {
+            address: Address::from(pk),
+            is_guest: false,
+        });
 
         let (tx, rx) = stream.split();
 
-        let res = Self {
+        let (status_tx, status) = watch::channel(ConnectionStatus::Connected);
+
+        let mut res = Self {
             tx: Arc::new(Mutex::new(tx)),
-            n: AtomicUsize::default(),
+            n: Arc::default(),
             pending_reqs: Arc::default(),
+            metrics,
+            clock,
+            decimal_places,
+            subscriptions: Arc::new(StdMutex::new(DEFAULT_SUBSCRIPTIONS.to_vec())),
+            last_keepalive: Arc::default(),
+            login,
+            initial_wallet: None,
+            activity: cfg.idle_timeout.map(|_| Arc::default()),
+            events: broadcast::channel(EVENT_BROADCAST_CAPACITY).0,
+            closing: Arc::default(),
+            status,
+            tasks: Vec::new(),
             _marker: PhantomData,
         };
 
-        let (send, recv) = tokio::sync::mpsc::channel(20);
+        let reconnect = cfg.reconnect.zip(connector);
+        let recv = res.spawn_incoming(rx, cfg.tap, reconnect, status_tx);
 
-        tokio::spawn(handle::handle_incoming(rx, res.pending_reqs.clone(), send));
+        if let (Some(timeout), Some(activity)) = (cfg.idle_timeout, &res.activity) {
+            let idle = tokio::spawn(close_when_idle(
+                timeout,
+                activity.clone(),
+                res.clock.clone(),
+                res.tx.clone(),
+                res.closing.clone(),
+            ));
 
-        for i in default_events.into_iter().filter(|v| !subs.contains(v)) {
-            let _ = res.unsubscribe(i).await;
+            res.tasks.push(idle.abort_handle());
         }
 
-        for i in subs.iter().filter(|v| !default_events.contains(v)) {
-            let _ = res.subscribe(*i).await;
-        }
+        let reconcile = async {
+            if !cfg.keep_defaults {
+                for i in default_events.into_iter().filter(|v| !subs.contains(v)) {
+                    let _ = res.unsubscribe(i).await;
+                }
+            }
+
+            for i in subs.iter().filter(|v| !default_events.contains(v)) {
+                let _ = res.subscribe(*i).await;
+            }
+        };
+
+        // Kromer2 authorizes the socket when it is started over HTTP, so there
+        // is no login response to read the wallet from
+        let me = async {
+            res.login?;
+
+            match res.make_request(WebSocketRequestInner::Me).await {
+                Ok(MessageResponseInner::Me { address }) => Some(address),
+                _ => None,
+            }
+        };
+
+        let ((), initial_wallet) = tokio::join!(reconcile, me);
+        res.initial_wallet = initial_wallet;
 
         (res, recv)
     }
 
+    /// The server time sent with the most recent keepalive, or `None` if one
+    /// hasn't been received yet. Comparing this against the local time gives a
+    /// rough measure of clock drift, offset by the latency of the socket.
+    #[must_use]
+    pub fn last_keepalive(&self) -> Option<DateTime<Utc>> {
+        self.last_keepalive.load().as_deref().copied()
+    }
+
+    /// The state of the socket's connection. The returned receiver can be
+    /// awaited for changes, such as to show "reconnecting (attempt 3)" while a
+    /// socket configured with [`WsConfig::reconnect`] is reconnecting.
+    #[must_use]
+    pub fn status(&self) -> watch::Receiver<ConnectionStatus> {
+        self.status.clone()
+    }
+
+    /// The subscription level the server last reported, without contacting it.
+    ///
+    /// This starts at [`DEFAULT_SUBSCRIPTIONS`] and is updated from the
+    /// response to every [`Self::subscribe`] and [`Self::unsubscribe`],
+    /// including those made while the client is created, so it stands in for
+    /// [`Self::currently_subscribed`], which Kromer2 never answers.
+    #[must_use]
+    pub fn subscriptions(&self) -> Vec<SubscriptionType> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Spawns the task reading from the socket, returning the receiver it sends
+    /// events to
+    fn spawn_incoming(
+        &mut self,
+        rx: SplitStream<KromerStream>,
+        tap: Option<FrameTap>,
+        reconnect: Option<(ReconnectPolicy, Reconnector)>,
+        status: watch::Sender<ConnectionStatus>,
+    ) -> Receiver<WebSocketEvent> {
+        let (event_tx, recv) = tokio::sync::mpsc::channel(20);
+
+        let incoming = handle::Incoming {
+            pending: self.pending_reqs.clone(),
+            event_tx,
+            broadcast: self.events.clone(),
+            metrics: self.metrics.clone(),
+            tap,
+            keepalive: self.last_keepalive.clone(),
+            activity: self.activity.clone(),
+            decimal_places: self.decimal_places,
+        };
+
+        let socket = Socket {
+            tx: self.tx.clone(),
+            n: self.n.clone(),
+            subscriptions: self.subscriptions.clone(),
+            closing: self.closing.clone(),
+            clock: self.clock.clone(),
+        };
+
+        let task = tokio::spawn(async move {
+            let mut rx = rx;
+
+            loop {
+                handle::handle_incoming(rx, &incoming).await;
+
+                let Some((policy, connector)) = &reconnect else {
+                    break;
+                };
+
+                if socket.closing.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match socket.reconnect(policy, connector, &status).await {
+                    Some(next) => rx = next,
+                    None => break,
+                }
+            }
+
+            let _ = status.send(ConnectionStatus::Closed);
+        });
+
+        self.tasks.push(task.abort_handle());
+
+        recv
+    }
+
+    /// Subscribes to [`SubscriptionType::Names`] and streams every update to
+    /// `name`, such as transfers and data changes. Other names are filtered
+    /// out on our side, as the server can't scope the subscription.
+    ///
+    /// Comparing the [`owner`](NameInfo::owner) of consecutive updates tells
+    /// transfers apart from data changes. The stream ends when the socket
+    /// closes, and skips updates if it falls far behind.
+    ///
+    /// # Errors
+    /// Errors if the subscription can't be made
+    ///
+    /// See [`WebSocketError`] for more info
+    pub async fn watch_name(
+        &self,
+        name: &Name,
+    ) -> Result<impl Stream<Item = NameInfo> + Send + use<M>, Error> {
+        // Subscribe to the broadcast first so no updates are missed
+        let rx = self.events.subscribe();
+        self.subscribe(SubscriptionType::Names).await?;
+
+        let name = name.to_string();
+
+        Ok(events(rx).filter_map(move |event| {
+            future::ready(match event {
+                WebSocketEvent::Name { name: info } if info.name == name => Some(info),
+                _ => None,
+            })
+        }))
+    }
+
     fn next_id(&self) -> usize {
         self.n.fetch_add(1, Ordering::Relaxed)
     }
@@ -189,6 +530,14 @@ impl<M: WsState> WsClient<M> {
 
         let msg = WebSocketRequest { id, inner: req }.into_message();
 
+        if let Some(metrics) = &self.metrics {
+            metrics.ws_message_sent(msg.len());
+        }
+
+        if let Some(activity) = &self.activity {
+            activity.notify_one();
+        }
+
         trace!("registered request {id}");
         let send_res = self.tx.lock().await.send(msg).await.context(WsNetSnafu);
 
@@ -202,14 +551,16 @@ impl<M: WsState> WsClient<M> {
         }
 
         // NOTE Timeout after 3s, maybe change or make a param when constructing WS connection
-        let req_res = timeout(Duration::from_secs(3), rx).await.map_or_else(
-            |_| Err(WebSocketError::TimeOut),
-            |v| v.map_err(|_| WebSocketError::RecvError),
-        )?;
+        let req_res = clock::timeout(self.clock.as_ref(), Duration::from_secs(3), rx)
+            .await
+            .map_or_else(
+                || Err(WebSocketError::TimeOut),
+                |v| v.map_err(|_| WebSocketError::RecvError),
+            )?;
 
         match req_res {
             WebSocketMessageInner::Error { error, message } => {
-                RawKristError { error, message }.parse()?;
+                RawKristError { error, message }.parse(None)?;
                 unreachable!()
             }
             WebSocketMessageInner::Response { responding_to } => Ok(responding_to),
@@ -217,23 +568,40 @@ impl<M: WsState> WsClient<M> {
         }
     }
 
-    /// Subscribes the socket to a new [`SubscriptionType`]
+    /// Subscribes the socket to a new [`SubscriptionType`], returning the new
+    /// subscription level.
+    ///
+    /// The client keeps track of the subscription level the server last
+    /// reported. If the socket is already subscribed to `event`, that level is
+    /// returned without contacting the server. This only reflects
+    /// subscriptions made through this client.
     ///
     /// # Errors
     /// Errors if there is an issue with the underlying socket
     #[instrument(skip(self))]
     pub async fn subscribe(&self, event: SubscriptionType) -> Result<Vec<SubscriptionType>, Error> {
+        if let Some(level) = self.cached_level(|subs| subs.contains(&event)) {
+            trace!("already subscribed, skipping request");
+            return Ok(level);
+        }
+
         let req = WebSocketRequestInner::Subscribe { event };
 
         let msg = self.make_request(req).await?;
 
         match msg {
-            MessageResponseInner::Subscribe { subscription_level } => Ok(subscription_level),
+            MessageResponseInner::Subscribe { subscription_level } => {
+                Ok(self.set_level(subscription_level))
+            }
             _ => Err(WebSocketError::InvalidType.into()),
         }
     }
 
-    /// Unsubscribes the socket from a [`SubscriptionType`]
+    /// Unsubscribes the socket from a [`SubscriptionType`], returning the new
+    /// subscription level.
+    ///
+    /// Like [`Self::subscribe`], this returns the last known level without
+    /// contacting the server if the socket is not subscribed to `event`.
     ///
     /// # Errors
     /// Errors if there is an issue with the underlying socket
@@ -242,17 +610,50 @@ impl<M: WsState> WsClient<M> {
         &self,
         event: SubscriptionType,
     ) -> Result<Vec<SubscriptionType>, Error> {
+        if let Some(level) = self.cached_level(|subs| !subs.contains(&event)) {
+            trace!("not subscribed, skipping request");
+            return Ok(level);
+        }
+
         let req = WebSocketRequestInner::Unsubscribe { event };
 
         let msg = self.make_request(req).await?;
 
         match msg {
-            MessageResponseInner::Subscribe { subscription_level } => Ok(subscription_level),
+            MessageResponseInner::Subscribe { subscription_level } => {
+                Ok(self.set_level(subscription_level))
+            }
             _ => Err(WebSocketError::InvalidType.into()),
         }
     }
 
-    /// DON'T USE THIS IT WILL ALWAYS TIME OUT
+    /// Returns a copy of the cached subscription level if `f` returns true for
+    /// it
+    fn cached_level(
+        &self,
+        f: impl FnOnce(&[SubscriptionType]) -> bool,
+    ) -> Option<Vec<SubscriptionType>> {
+        let subs = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        f(&subs).then(|| subs.clone())
+    }
+
+    fn set_level(&self, level: Vec<SubscriptionType>) -> Vec<SubscriptionType> {
+        level.clone_into(
+            &mut self
+                .subscriptions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        level
+    }
+
+    /// DON'T USE THIS IT WILL ALWAYS TIME OUT, use [`Self::subscriptions`]
+    /// instead
     ///
     /// # Errors
     /// Always errors, Kromer2 (for a reason I can't fathom) never responds to this but also
@@ -366,6 +767,31 @@ impl WsClient<Auth> {
         }
     }
 
+    /// Who this socket is authorized as. This is recorded when the socket is
+    /// opened, so unlike [`Self::me`] it doesn't contact the server, but it
+    /// doesn't include the wallet's balance either.
+    ///
+    /// # Panics
+    /// Never panics in practice. If it does, it is an issue at the crate level
+    #[must_use]
+    pub const fn login_info(&self) -> LoginInfo {
+        // Safety:
+        // Auth clients can only be created from a config with a private key, which always sets this
+        #[allow(clippy::expect_used)]
+        self.login.expect("Auth client created without login info")
+    }
+
+    /// The authorized [`Wallet`] as it was when the socket was opened, saving a
+    /// call to [`Self::me`] right after connecting. Its balance isn't kept up
+    /// to date.
+    ///
+    /// This is fetched while the socket is being set up, and is `None` if that
+    /// request failed.
+    #[must_use]
+    pub const fn initial_wallet(&self) -> Option<Wallet> {
+        self.initial_wallet
+    }
+
     /// Fetches information about the currently authorized user
     ///
     /// # Errors
@@ -379,14 +805,46 @@ impl WsClient<Auth> {
     }
 }
 
+/// Who a [`WsClient<Auth>`] is authorized as, see [`WsClient::login_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginInfo {
+    /// The [`Address`] of the authorized wallet
+    pub address: Address,
+    /// Whether the socket is connected as a guest. Always `false` for an
+    /// authorized socket
+    pub is_guest: bool,
+}
+
 /// A configuration for building a [`WsClient`]
 #[derive(Debug, Serialize, Default)]
 pub struct WsConfig<M: WsState> {
     pub(crate) pk: Option<PrivateKey>,
     pub(crate) subscriptions: Vec<SubscriptionType>,
+    #[serde(skip)]
+    pub(crate) tap: Option<FrameTap>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) reconnect: Option<ReconnectPolicy>,
+    pub(crate) keep_defaults: bool,
     _marker: PhantomData<M>,
 }
 
+/// A callback handed the raw text of every frame received. See
+/// [`WsConfig::tap_frames`]
+#[derive(Clone)]
+pub(crate) struct FrameTap(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl FrameTap {
+    pub(crate) fn call(&self, frame: &str) {
+        (self.0)(frame);
+    }
+}
+
+impl std::fmt::Debug for FrameTap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FrameTap")
+    }
+}
+
 impl<M: WsState> WsConfig<M> {
     /// Adds a subscription to [`Self`]
     #[must_use]
@@ -396,6 +854,61 @@ impl<M: WsState> WsConfig<M> {
         }
         self
     }
+
+    /// Passes the raw text of every frame the socket receives to `tap` before
+    /// it is processed, including frames that fail to deserialize and would
+    /// otherwise be dropped. Useful for debugging changes to the Kromer2 wire
+    /// format against a running server.
+    ///
+    /// `tap` is called from the task reading the socket, so it should return
+    /// quickly.
+    #[must_use]
+    pub fn tap_frames(mut self, tap: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.tap = Some(FrameTap(Arc::new(tap)));
+        self
+    }
+
+    /// Closes the socket once no requests have been sent and no messages have
+    /// been received for `timeout`. The event [`Receiver`] will then return
+    /// `None`, as it does whenever the socket closes.
+    ///
+    /// Kromer2 periodically sends keepalives and pings to every socket. These
+    /// are not counted as activity, so a socket that is only being kept alive
+    /// by the server will still be closed. Events, such as new transactions,
+    /// are counted.
+    #[must_use]
+    pub const fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Reconnects the socket if it drops, waiting between attempts as set out
+    /// by `policy`. Events keep arriving on the same [`Receiver`], which
+    /// returns `None` once `policy` runs out of attempts. Sockets aren't
+    /// reconnected by default.
+    ///
+    /// The subscription level is restored after reconnecting, but events sent
+    /// while disconnected are missed and requests made while disconnected
+    /// fail. Track progress with [`WsClient::status`]. Closing the socket
+    /// yourself, or through [`Self::idle_timeout`], never reconnects it.
+    #[must_use]
+    pub const fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Leaves the socket subscribed to [`DEFAULT_SUBSCRIPTIONS`] instead of
+    /// unsubscribing from those that weren't asked for, saving up to two round
+    /// trips on connect. Subscriptions added with [`Self::subscribe`] are still
+    /// requested.
+    ///
+    /// Events for the defaults will then arrive on the event [`Receiver`], so
+    /// this suits clients that ignore events or want them anyway.
+    #[must_use]
+    pub const fn keep_default_subscriptions(mut self) -> Self {
+        self.keep_defaults = true;
+        self
+    }
 }
 
 impl WsConfig<Guest> {
@@ -405,6 +918,10 @@ impl WsConfig<Guest> {
         Self {
             pk: None,
             subscriptions: Vec::new(),
+            tap: None,
+            idle_timeout: None,
+            reconnect: None,
+            keep_defaults: false,
             _marker: PhantomData,
         }
     }
@@ -416,7 +933,114 @@ impl WsConfig<Guest> {
         WsConfig::<Auth> {
             pk: Some(pk),
             subscriptions: self.subscriptions,
+            tap: self.tap,
+            idle_timeout: self.idle_timeout,
+            reconnect: self.reconnect,
+            keep_defaults: self.keep_defaults,
             _marker: PhantomData,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{WsClient, WsConfig};
+    use crate::{clock::SystemClock, mock, model::ws::SubscriptionType};
+    use futures_util::StreamExt;
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn drop_closes_socket() {
+        let (stream, server) = mock::websocket(|mut ws| async move { ws.next().await }).await;
+
+        // Matches the server's defaults, so no requests are made on creation
+        let cfg = WsConfig::new()
+            .subscribe(SubscriptionType::Blocks)
+            .subscribe(SubscriptionType::OwnTransactions);
+        let (client, mut events) =
+            WsClient::new_from_config(stream, cfg, None, Arc::new(SystemClock), None, None).await;
+
+        drop(client);
+
+        assert!(events.recv().await.is_none());
+        assert!(matches!(server.await.unwrap(), Some(Ok(Message::Close(_)))));
+    }
+
+    #[tokio::test]
+    async fn tracks_subscriptions() {
+        use futures_util::SinkExt;
+
+        // Answers subscribe and unsubscribe requests like Kromer2 does
+        let (stream, server) = mock::websocket(|mut ws| async move {
+            let mut level = super::DEFAULT_SUBSCRIPTIONS.to_vec();
+
+            while let Some(Ok(Message::Text(req))) = ws.next().await {
+                let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+                let event: SubscriptionType = serde_json::from_value(req["event"].clone()).unwrap();
+
+                match req["type"].as_str().unwrap() {
+                    "subscribe" if !level.contains(&event) => level.push(event),
+                    "unsubscribe" => level.retain(|v| *v != event),
+                    _ => {}
+                }
+
+                let res = serde_json::json!({
+                    "id": req["id"],
+                    "type": "response",
+                    "responding_to": "subscribe",
+                    "subscription_level": level,
+                });
+                ws.send(Message::text(res.to_string())).await.unwrap();
+            }
+        })
+        .await;
+
+        let cfg = WsConfig::new().subscribe(SubscriptionType::Names);
+        let (client, _events) =
+            WsClient::new_from_config(stream, cfg, None, Arc::new(SystemClock), None, None).await;
+        assert_eq!(client.subscriptions(), [SubscriptionType::Names]);
+
+        client
+            .subscribe(SubscriptionType::Transactions)
+            .await
+            .unwrap();
+        let level = client.unsubscribe(SubscriptionType::Names).await.unwrap();
+        assert_eq!(level, [SubscriptionType::Transactions]);
+        assert_eq!(client.subscriptions(), level);
+
+        drop(client);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn rounds_event_amounts() {
+        use super::WebSocketEvent;
+        use futures_util::SinkExt;
+        use rust_decimal::Decimal;
+
+        let (stream, server) = mock::websocket(|mut ws| async move {
+            let tx = mock::transaction(1, "kaaaaaaaaa").replace(r#""value":1"#, r#""value":1.105"#);
+            let frame = format!(r#"{{"type":"event","event":"transaction","transaction":{tx}}}"#);
+
+            ws.send(Message::text(frame)).await.unwrap();
+            ws.next().await
+        })
+        .await;
+
+        let cfg = WsConfig::new()
+            .subscribe(SubscriptionType::Blocks)
+            .subscribe(SubscriptionType::OwnTransactions);
+        let (client, mut events) =
+            WsClient::new_from_config(stream, cfg, None, Arc::new(SystemClock), Some(2), None)
+                .await;
+
+        let Some(WebSocketEvent::Transaction { transaction }) = events.recv().await else {
+            panic!("expected a transaction event");
+        };
+        assert_eq!(transaction.value, Decimal::new(111, 2));
+
+        drop(client);
+        server.abort();
+    }
+}
@@ -3,7 +3,7 @@ use tokio_tungstenite::tungstenite;
 
 /// Errors thrown when working with the Kromer2 websocket API
 #[derive(Debug, Snafu)]
-#[snafu(visibility(pub(super)))]
+#[snafu(visibility(pub(crate)))]
 #[allow(missing_docs)]
 pub enum WebSocketError {
     /// Experienced an issue receiving the websocket response. Most likely because the other end of
@@ -15,10 +15,18 @@ pub enum WebSocketError {
     WsNetError {
         #[snafu(source(from(tungstenite::Error, Box::new)))]
         source: Box<tungstenite::Error>,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
     },
     /// Couldn't deserialize value into a model
     #[snafu(display("Failed to deserialize response into model"))]
-    MalformedResponse { source: serde_json::Error },
+    MalformedResponse {
+        source: serde_json::Error,
+        #[cfg(feature = "location")]
+        #[snafu(implicit)]
+        location: snafu::Location,
+    },
     /// Type of message received did not align with the expected response type for the request
     #[snafu(display("Recieved incorrect response type for id"))]
     InvalidType,
@@ -1,32 +1,70 @@
-use crate::{model::ws::WebSocketEvent, ws::MalformedResponseSnafu};
+use crate::{
+    metrics::Metrics,
+    model::{decimal, ws::WebSocketEvent},
+    ws::{FrameTap, MalformedResponseSnafu},
+};
 
 use super::messages::{WebSocketMessage, WebSocketMessageInner};
+use arc_swap::ArcSwapOption;
+use chrono::{DateTime, Utc};
 use futures_util::{StreamExt, stream::SplitStream};
 use scc::HashMap;
 use snafu::ResultExt;
 use std::{fmt::Debug, sync::Arc};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::{mpsc::Sender, oneshot},
+    sync::{Notify, broadcast, mpsc::Sender, oneshot},
 };
 use tokio_tungstenite::{WebSocketStream, tungstenite::Message};
 use tracing::{debug, trace};
 use tracing::{error, instrument, warn};
 
+/// Everything the task reading a socket shares with its [`WsClient`](super::WsClient)
+pub struct Incoming {
+    pub pending: Arc<HashMap<usize, oneshot::Sender<WebSocketMessageInner>>>,
+    pub event_tx: Sender<WebSocketEvent>,
+    pub broadcast: broadcast::Sender<WebSocketEvent>,
+    pub metrics: Option<Arc<dyn Metrics>>,
+    pub tap: Option<FrameTap>,
+    pub keepalive: Arc<ArcSwapOption<DateTime<Utc>>>,
+    pub activity: Option<Arc<Notify>>,
+    /// Amounts in frames are rounded to this many decimal places, if set
+    pub decimal_places: Option<u32>,
+}
+
 #[instrument(name = "handle_ws_incoming", skip_all)]
 pub async fn handle_incoming(
     mut rx: SplitStream<WebSocketStream<impl AsyncRead + AsyncWrite + Unpin + Debug>>,
-    pending: Arc<HashMap<usize, oneshot::Sender<WebSocketMessageInner>>>,
-    event_tx: Sender<WebSocketEvent>,
+    shared: &Incoming,
 ) {
+    let Incoming {
+        pending,
+        event_tx,
+        broadcast,
+        metrics,
+        tap,
+        keepalive,
+        activity,
+        decimal_places,
+    } = shared;
+
     while let Some(res) = rx.next().await {
+        if let (Some(metrics), Ok(frame)) = (&metrics, &res) {
+            metrics.ws_message_received(frame.len());
+        }
+
         // trace!("ws message: {res:?}");
         let msg = match res {
             Ok(Message::Text(b)) => {
                 // trace!("ws text message: {}", b.as_str());
+                if let Some(tap) = &tap {
+                    tap.call(b.as_str());
+                }
 
-                let res = serde_json::from_str::<WebSocketMessage>(b.as_str())
-                    .context(MalformedResponseSnafu);
+                let res = decimal::with_precision(*decimal_places, || {
+                    serde_json::from_str::<WebSocketMessage>(b.as_str())
+                })
+                .context(MalformedResponseSnafu);
 
                 if let Ok(m) = res {
                     trace!("received valid message");
@@ -58,10 +96,28 @@ pub async fn handle_incoming(
             }
         };
 
+        if let Some(activity) = &activity
+            && !matches!(
+                msg.msg,
+                WebSocketMessageInner::KeepAlive { .. } | WebSocketMessageInner::Hello
+            )
+        {
+            activity.notify_one();
+        }
+
         match (msg.id, msg.msg) {
             (_, WebSocketMessageInner::Event { event }) => {
+                if broadcast.receiver_count() > 0 {
+                    let _ = broadcast.send(event.clone());
+                }
+
                 let _ = event_tx.send(event).await;
             }
+            (_, WebSocketMessageInner::KeepAlive { server_time }) => {
+                if let Some(time) = server_time {
+                    keepalive.store(Some(Arc::new(time)));
+                }
+            }
             (Some(n), inner) => {
                 if let Some((_, os)) = pending.remove_async(&n).await
                     && os.send(inner).is_err()
@@ -69,10 +125,9 @@ pub async fn handle_incoming(
                     warn!("failed to pass message");
                 }
             }
-            // We ignore these two because they need to be handled, but not beyond
-            // deserialization. KeepAlive is always bundled with a ping which we handle above.
+            // We ignore this because it needs to be handled, but not beyond deserialization.
             // Hello is only received on startup and we don't do anything with it.
-            (None, WebSocketMessageInner::Hello | WebSocketMessageInner::KeepAlive) => (),
+            (None, WebSocketMessageInner::Hello) => (),
             (None, inner) => warn!("Received untagged response: {inner:#?}"),
         }
     }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tokio_tungstenite::tungstenite::Message;
@@ -42,7 +43,10 @@ pub enum WebSocketMessageInner {
         error: String,
         message: String,
     },
-    KeepAlive,
+    KeepAlive {
+        #[serde(default)]
+        server_time: Option<DateTime<Utc>>,
+    },
 
     Response {
         #[serde(flatten)]
@@ -108,3 +112,29 @@ pub enum WebSocketRequestInner<'a> {
     GetSubscriptionLevel,
     Me,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{WebSocketMessage, WebSocketMessageInner};
+
+    #[test]
+    fn keepalive_server_time() {
+        let msg: WebSocketMessage =
+            serde_json::from_str(r#"{"type":"keepalive","server_time":"2025-01-01T00:00:00Z"}"#)
+                .unwrap();
+
+        assert!(matches!(
+            msg.msg,
+            WebSocketMessageInner::KeepAlive {
+                server_time: Some(_)
+            }
+        ));
+
+        let msg: WebSocketMessage = serde_json::from_str(r#"{"type":"keepalive"}"#).unwrap();
+
+        assert!(matches!(
+            msg.msg,
+            WebSocketMessageInner::KeepAlive { server_time: None }
+        ));
+    }
+}
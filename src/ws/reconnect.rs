@@ -0,0 +1,240 @@
+use super::{
+    DEFAULT_SUBSCRIPTIONS, KromerStream,
+    messages::{WebSocketRequest, WebSocketRequestInner},
+};
+use crate::{Error, clock::Clock, model::ws::SubscriptionType};
+use futures_util::{
+    SinkExt, StreamExt,
+    future::BoxFuture,
+    stream::{SplitSink, SplitStream},
+};
+use serde::Serialize;
+use std::{
+    sync::{
+        Arc, Mutex as StdMutex, PoisonError,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::{Mutex, watch};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+
+/// Opens a fresh socket to the server a [`WsClient`](super::WsClient) was
+/// created from, authorized the same way
+pub type Reconnector =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<KromerStream, Error>> + Send + Sync>;
+
+/// Controls how a [`WsClient`](super::WsClient) reconnects after its socket
+/// drops. See [`WsConfig::reconnect`](super::WsConfig::reconnect).
+///
+/// Before attempt `n` the client waits
+/// `initial_delay * multiplier^(n - 1)`, capped at `max_delay`. Once
+/// `max_attempts` have failed it gives up, and the event
+/// [`Receiver`](tokio::sync::mpsc::Receiver) returns `None` as it does when
+/// the socket closes normally.
+///
+/// ```rust
+/// # use kromer_api::ws::ReconnectPolicy;
+/// # use std::time::Duration;
+/// let policy = ReconnectPolicy::new()
+///     .initial_delay(Duration::from_millis(500))
+///     .max_attempts(Some(5));
+///
+/// assert_eq!(policy.delay(1), Duration::from_millis(500));
+/// assert_eq!(policy.delay(3), Duration::from_secs(2));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ReconnectPolicy {
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    /// Creates a policy that starts at a 1 second delay, doubles it after each
+    /// failed attempt up to 1 minute, and gives up after 10 attempts
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_delay: Duration::from_mins(1),
+            max_attempts: Some(10),
+        }
+    }
+
+    /// Sets the delay before the first attempt
+    #[must_use]
+    pub const fn initial_delay(mut self, v: Duration) -> Self {
+        self.initial_delay = v;
+        self
+    }
+
+    /// Sets how much the delay grows after each failed attempt. Values below
+    /// 1 are treated as 1, giving a constant delay.
+    #[must_use]
+    pub const fn multiplier(mut self, v: f64) -> Self {
+        self.multiplier = v;
+        self
+    }
+
+    /// Sets the longest the client will wait between attempts
+    #[must_use]
+    pub const fn max_delay(mut self, v: Duration) -> Self {
+        self.max_delay = v;
+        self
+    }
+
+    /// Sets how many attempts are made before giving up, or `None` to retry
+    /// forever
+    #[must_use]
+    pub const fn max_attempts(mut self, v: Option<u32>) -> Self {
+        self.max_attempts = v;
+        self
+    }
+
+    /// The delay before attempt number `attempt`, counting from 1
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let factor = self.multiplier.max(1.0).powi(exp);
+
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Whether attempt number `attempt` may be made
+    pub(super) fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt <= max)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The state of a [`WsClient`](super::WsClient)'s connection. See
+/// [`WsClient::status`](super::WsClient::status)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// The socket is open
+    Connected,
+    /// The socket dropped and the client is trying to reconnect
+    Reconnecting {
+        /// The current attempt, counting from 1
+        attempt: u32,
+    },
+    /// The socket is closed for good, either because it was closed on purpose
+    /// or every reconnect attempt failed
+    Closed,
+}
+
+/// The parts of a [`WsClient`](super::WsClient) the task reading its socket
+/// needs to replace the socket with a new one
+pub(super) struct Socket {
+    pub(super) tx: Arc<Mutex<SplitSink<KromerStream, Message>>>,
+    pub(super) n: Arc<AtomicUsize>,
+    pub(super) subscriptions: Arc<StdMutex<Vec<SubscriptionType>>>,
+    pub(super) closing: Arc<AtomicBool>,
+    pub(super) clock: Arc<dyn Clock>,
+}
+
+impl Socket {
+    /// Tries to open a new socket following `policy`, returning its read half
+    /// or `None` once every attempt has failed or the client is closed
+    pub(super) async fn reconnect(
+        &self,
+        policy: &ReconnectPolicy,
+        connector: &Reconnector,
+        status: &watch::Sender<ConnectionStatus>,
+    ) -> Option<SplitStream<KromerStream>> {
+        let mut attempt = 1;
+
+        while policy.allows(attempt) {
+            let _ = status.send(ConnectionStatus::Reconnecting { attempt });
+            self.clock.sleep(policy.delay(attempt)).await;
+
+            if self.closing.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            match connector().await {
+                Ok(stream) => {
+                    debug!("reconnected after {attempt} attempts");
+
+                    let (tx, rx) = stream.split();
+                    *self.tx.lock().await = tx;
+
+                    self.resubscribe().await;
+                    let _ = status.send(ConnectionStatus::Connected);
+
+                    return Some(rx);
+                }
+                Err(e) => warn!("reconnect attempt {attempt} failed: {e}"),
+            }
+
+            attempt += 1;
+        }
+
+        None
+    }
+
+    /// Restores the subscription level the client had before the socket
+    /// dropped. A new socket starts with the server's defaults.
+    async fn resubscribe(&self) {
+        let subs = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let unsubscribe = DEFAULT_SUBSCRIPTIONS
+            .into_iter()
+            .filter(|v| !subs.contains(v))
+            .map(|event| WebSocketRequestInner::Unsubscribe { event });
+
+        let subscribe = subs
+            .iter()
+            .filter(|v| !DEFAULT_SUBSCRIPTIONS.contains(v))
+            .map(|&event| WebSocketRequestInner::Subscribe { event });
+
+        let mut tx = self.tx.lock().await;
+
+        // Responses are ignored, as no request is waiting on these ids
+        for inner in unsubscribe.chain(subscribe) {
+            let id = self.n.fetch_add(1, Ordering::Relaxed);
+            let _ = tx.send(WebSocketRequest { id, inner }.into_message()).await;
+        }
+
+        drop(tx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconnectPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff() {
+        let policy = ReconnectPolicy::new()
+            .max_delay(Duration::from_secs(5))
+            .max_attempts(Some(3));
+
+        let delays: Vec<_> = (1..=5).map(|n| policy.delay(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+
+        assert!(policy.allows(3));
+        assert!(!policy.allows(4));
+        assert!(ReconnectPolicy::new().max_attempts(None).allows(u32::MAX));
+
+        let flat = ReconnectPolicy::new().multiplier(0.5);
+        assert_eq!(flat.delay(4), Duration::from_secs(1));
+    }
+}